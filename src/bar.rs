@@ -0,0 +1,172 @@
+use crate::color::{AutoColor, Band, Color};
+use crate::temps::CoreTemp;
+use crate::units::{final_temp, Unit};
+
+/// The header that starts the i3bar protocol, see i3bar-protocol(7). It's
+/// followed by an infinite JSON array, with one array of blocks per update.
+pub const I3BAR_HEADER: &str = "{\"version\":1}";
+
+/// A block of the i3bar protocol, which is also what swaybar speaks.
+pub struct Block {
+    pub full_text: String,
+    pub short_text: Option<String>,
+    pub color: Option<String>,
+    pub urgent: bool,
+    pub name: String,
+    pub instance: Option<String>,
+    pub pango: bool,
+}
+
+impl Block {
+    pub fn to_json(&self) -> String {
+        let mut fields = vec![format!("\"full_text\":{}", json_string(&self.full_text))];
+
+        if let Some(short_text) = &self.short_text {
+            fields.push(format!("\"short_text\":{}", json_string(short_text)));
+        }
+
+        if let Some(color) = &self.color {
+            fields.push(format!("\"color\":{}", json_string(color)));
+        }
+
+        if self.urgent {
+            fields.push("\"urgent\":true".into());
+        }
+
+        fields.push(format!("\"name\":{}", json_string(&self.name)));
+
+        if let Some(instance) = &self.instance {
+            fields.push(format!("\"instance\":{}", json_string(instance)));
+        }
+
+        if self.pango {
+            fields.push("\"markup\":\"pango\"".into());
+        }
+
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// The output of a Waybar custom module with return-type json.
+pub struct WaybarModule {
+    pub text: String,
+    pub tooltip: Option<String>,
+    pub band: Option<Band>,
+    pub percentage: Option<f64>,
+}
+
+impl WaybarModule {
+    /// The class, which is also the alt, so both CSS and format-icons can
+    /// follow the band of the hottest core.
+    fn class(&self) -> Option<&'static str> {
+        self.band.as_ref().map(|band| match band {
+            Band::Normal => "normal",
+            Band::Warm => "warm",
+            Band::Hot => "critical",
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut fields = vec![format!("\"text\":{}", json_string(&self.text))];
+
+        if let Some(tooltip) = &self.tooltip {
+            fields.push(format!("\"tooltip\":{}", json_string(tooltip)));
+        }
+
+        if let Some(class) = self.class() {
+            fields.push(format!("\"class\":\"{}\"", class));
+        }
+
+        if let Some(percentage) = self.percentage {
+            fields.push(format!("\"percentage\":{}", percentage.round() as u64));
+        }
+
+        if let Some(class) = self.class() {
+            fields.push(format!("\"alt\":\"{}\"", class));
+        }
+
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// Quotes and escapes a string as a JSON string literal.
+pub fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// How close the hottest core is to its critical temperature, in percent.
+/// None if no core has a critical temperature.
+pub fn hottest_percent(ct: &CoreTemp) -> Option<f64> {
+    let cores = ct.get_cores(None).ok()?;
+
+    cores
+        .into_iter()
+        .filter_map(|core| {
+            let temp = ct.get_temp(core).ok()?;
+            let crit = ct.get_critical(core).ok().filter(|crit| *crit > 0)?;
+            Some(temp as f64 / crit as f64 * 100.0)
+        })
+        .reduce(f64::max)
+}
+
+/// How the hottest core colors a block or module, following the
+/// --auto-color, --band-colors and temperature units of its segments.
+pub struct Bands {
+    pub auto_color: AutoColor,
+    pub colors: [Color; 3],
+    pub base_unit: Unit,
+    pub target_unit: Option<Unit>,
+}
+
+impl Bands {
+    /// The highest band of any core. Absolute thresholds are compared with
+    /// the temperature in the unit it's printed in, like --auto-color does.
+    pub fn hottest(&self, ct: &CoreTemp) -> Option<Band> {
+        let cores = ct.get_cores(None).ok()?;
+
+        cores
+            .into_iter()
+            .filter_map(|core| {
+                let temp = ct.get_temp(core).ok()? as f64;
+
+                let percent_of_crit = ct
+                    .get_critical(core)
+                    .ok()
+                    .filter(|crit| *crit > 0)
+                    .map(|crit| temp * 100.0 / crit as f64);
+
+                let final_temp = final_temp(temp, &self.base_unit, self.target_unit.as_ref());
+
+                self.auto_color.band(final_temp, percent_of_crit)
+            })
+            .max()
+    }
+}
+
+/// Whether the critical alarm of any core is set.
+pub fn any_alarm(ct: &CoreTemp) -> bool {
+    let cores = match ct.get_cores(None) {
+        Ok(cores) => cores,
+        Err(_) => return false,
+    };
+
+    cores
+        .into_iter()
+        .any(|core| ct.get_critical_alarm(core).is_ok_and(|alarm| alarm != 0))
+}
@@ -0,0 +1,194 @@
+use std::io::IsTerminal;
+
+use crate::markup::Dialect;
+
+pub const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+#[derive(Clone, Default, PartialEq)]
+pub enum Color {
+    #[default]
+    Default,
+    // The 16 named colors, 0-7 normal and 8-15 bright.
+    Named(u8),
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Parses a color name (red, bright-red), a 256 color palette index
+    /// (0-255), or a #rrggbb truecolor hex code. default resets the color.
+    pub fn from_str(s: &str) -> Option<Color> {
+        if matches!(s, "default" | "none" | "reset") {
+            return Some(Color::Default);
+        }
+
+        if let Some(hex) = s.strip_prefix('#') {
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+
+        if let Ok(index) = s.parse::<u8>() {
+            return Some(Color::Indexed(index));
+        }
+
+        let (name, bright) = match s.strip_prefix("bright-") {
+            Some(name) => (name, 8),
+            None => (s, 0),
+        };
+
+        COLOR_NAMES
+            .iter()
+            .position(|n| *n == name)
+            .map(|n| Color::Named(n as u8 + bright))
+    }
+
+    /// The color as a #rrggbb hex code, for markup that only understands
+    /// those. Named and indexed colors are looked up in xterm's palette.
+    pub fn to_hex(&self) -> Option<String> {
+        const NAMED: [(u8, u8, u8); 16] = [
+            (0x00, 0x00, 0x00),
+            (0xcd, 0x00, 0x00),
+            (0x00, 0xcd, 0x00),
+            (0xcd, 0xcd, 0x00),
+            (0x00, 0x00, 0xee),
+            (0xcd, 0x00, 0xcd),
+            (0x00, 0xcd, 0xcd),
+            (0xe5, 0xe5, 0xe5),
+            (0x7f, 0x7f, 0x7f),
+            (0xff, 0x00, 0x00),
+            (0x00, 0xff, 0x00),
+            (0xff, 0xff, 0x00),
+            (0x5c, 0x5c, 0xff),
+            (0xff, 0x00, 0xff),
+            (0x00, 0xff, 0xff),
+            (0xff, 0xff, 0xff),
+        ];
+
+        // The 6x6x6 color cube, followed by a 24 step grayscale ramp.
+        let indexed = |n: u8| match n {
+            0..=15 => NAMED[n as usize],
+            16..=231 => {
+                let level = |l: u8| if l == 0 { 0 } else { 55 + l * 40 };
+                let n = n - 16;
+                (level(n / 36), level(n / 6 % 6), level(n % 6))
+            }
+            _ => {
+                let gray = 8 + (n - 232) * 10;
+                (gray, gray, gray)
+            }
+        };
+
+        let (r, g, b) = match self {
+            Color::Default => return None,
+            Color::Named(n) | Color::Indexed(n) => indexed(*n),
+            Color::Rgb(r, g, b) => (*r, *g, *b),
+        };
+
+        Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+    }
+
+    /// The SGR escape sequence that sets this color as the foreground, or as
+    /// the background if background is true.
+    pub fn to_ansi(&self, background: bool) -> String {
+        let base = if background { 40 } else { 30 };
+
+        match self {
+            Color::Default => format!("\x1b[{}m", base + 9),
+            Color::Named(n) if *n < 8 => format!("\x1b[{}m", base + n),
+            Color::Named(n) => format!("\x1b[{}m", base + 60 + (n - 8)),
+            Color::Indexed(n) => format!("\x1b[{};5;{}m", base + 8, n),
+            Color::Rgb(r, g, b) => format!("\x1b[{};2;{};{};{}m", base + 8, r, g, b),
+        }
+    }
+}
+
+/// Whether colors and emphasis are emitted. For ANSI escape sequences, auto
+/// follows the NO_COLOR convention (https://no-color.org), and leaves colors
+/// out when stdout isn't a TTY. Status bar markup is meant to be read by a
+/// bar rather than a terminal, so auto always emits it.
+#[derive(Clone, Copy)]
+pub enum ColorMode {
+    Always,
+    Auto,
+    Never,
+}
+
+impl ColorMode {
+    pub fn is_enabled(&self, dialect: &Dialect) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto if *dialect != Dialect::Ansi => true,
+            ColorMode::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                !no_color && std::io::stdout().is_terminal()
+            }
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<ColorMode> {
+        match s {
+            "always" | "yes" | "on" => Some(ColorMode::Always),
+            "auto" => Some(ColorMode::Auto),
+            "never" | "no" | "off" => Some(ColorMode::Never),
+            _ => None,
+        }
+    }
+}
+
+/// The temperature bands that automatic coloring picks a color from.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Band {
+    Normal,
+    Warm,
+    Hot,
+}
+
+impl Band {
+    /// The band's color out of the normal, warm and hot colors, as set by
+    /// --band-colors.
+    pub fn color(self, colors: &[Color; 3]) -> &Color {
+        match self {
+            Band::Normal => &colors[0],
+            Band::Warm => &colors[1],
+            Band::Hot => &colors[2],
+        }
+    }
+}
+
+/// How temperature segments are automatically colored, if at all. Absolute
+/// thresholds are in the unit the temperature is printed in, and critical
+/// thresholds are percentages of the sensor's critical temperature.
+#[derive(Clone)]
+pub enum AutoColor {
+    Off,
+    Absolute { warm: f64, hot: f64 },
+    Critical { warm: f64, hot: f64 },
+}
+
+impl AutoColor {
+    /// Picks the band of a temperature, given in the unit it's printed in,
+    /// and as a percentage of the sensor's critical temperature, if the
+    /// sensor has one. Critical bands can't apply without one.
+    pub fn band(&self, temp: f64, percent_of_crit: Option<f64>) -> Option<Band> {
+        let (value, warm, hot) = match self {
+            AutoColor::Off => return None,
+            AutoColor::Absolute { warm, hot } => (temp, *warm, *hot),
+            AutoColor::Critical { warm, hot } => (percent_of_crit?, *warm, *hot),
+        };
+
+        Some(if value >= hot {
+            Band::Hot
+        } else if value >= warm {
+            Band::Warm
+        } else {
+            Band::Normal
+        })
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow as ah;
+use std::collections::HashMap;
+use std::fs;
+
+use std::path::{Path, PathBuf};
+
+use crate::sysfs::{self, SYSFS_CPU};
+
+struct CpuFreqPolicy {
+    scaling_cur_freq: PathBuf,
+    cpuinfo_min_freq: PathBuf,
+    cpuinfo_max_freq: PathBuf,
+}
+
+fn read_khz(path: &Path) -> ah::Result<u64> {
+    let khz = fs::read_to_string(path)?;
+    Ok(khz.trim().parse::<u64>()?)
+}
+
+/// Per logical CPU frequencies, in kHz, from cpuN/cpufreq.
+pub struct CpuFreq {
+    cpus: HashMap<u64, CpuFreqPolicy>,
+}
+
+impl CpuFreq {
+    fn cpu(&self, cpu: u64) -> ah::Result<&CpuFreqPolicy> {
+        self.cpus
+            .get(&cpu)
+            .ok_or(ah::anyhow!("CPU {} has no cpufreq", cpu))
+    }
+
+    fn read_all(&self) -> ah::Result<Vec<u64>> {
+        let mut frequencies = Vec::with_capacity(self.cpus.len());
+
+        for cpu in self.cpus.values() {
+            frequencies.push(read_khz(&cpu.scaling_cur_freq)?);
+        }
+
+        Ok(frequencies)
+    }
+
+    pub fn get_cpus(&self) -> Vec<u64> {
+        let mut cpus: Vec<u64> = self.cpus.keys().copied().collect();
+        cpus.sort();
+        cpus
+    }
+
+    pub fn get_freq(&self, cpu: u64) -> ah::Result<u64> {
+        read_khz(&self.cpu(cpu)?.scaling_cur_freq)
+    }
+
+    pub fn get_hw_min(&self, cpu: u64) -> ah::Result<u64> {
+        read_khz(&self.cpu(cpu)?.cpuinfo_min_freq)
+    }
+
+    pub fn get_hw_max(&self, cpu: u64) -> ah::Result<u64> {
+        read_khz(&self.cpu(cpu)?.cpuinfo_max_freq)
+    }
+
+    pub fn get_average(&self) -> ah::Result<f64> {
+        let frequencies = self.read_all()?;
+        Ok(frequencies.iter().sum::<u64>() as f64 / frequencies.len() as f64)
+    }
+
+    pub fn get_median(&self) -> ah::Result<f64> {
+        let mut frequencies = self.read_all()?;
+
+        frequencies.sort();
+
+        let median = if frequencies.len().is_multiple_of(2) {
+            let center = frequencies.len() / 2;
+
+            (frequencies[center - 1] + frequencies[center]) as f64 / 2.0
+        } else {
+            frequencies[frequencies.len() / 2] as f64
+        };
+
+        Ok(median)
+    }
+
+    pub fn get_min(&self) -> ah::Result<u64> {
+        let frequencies = self.read_all()?;
+        Ok(frequencies.into_iter().min().unwrap_or(u64::MAX))
+    }
+
+    pub fn get_max(&self) -> ah::Result<u64> {
+        let frequencies = self.read_all()?;
+        Ok(frequencies.into_iter().max().unwrap_or(u64::MIN))
+    }
+
+    pub fn try_new(sysfs_root: &Path) -> ah::Result<CpuFreq> {
+        let cpu_path = sysfs_root.join(SYSFS_CPU);
+
+        let mut cpus = HashMap::<u64, CpuFreqPolicy>::new();
+
+        for (cpu_n, dir_path) in sysfs::numbered_dirs(&cpu_path, "cpu")? {
+            let cpufreq = dir_path.join("cpufreq");
+            let scaling_cur_freq = cpufreq.join("scaling_cur_freq");
+
+            // Offline CPUs have no cpufreq directory.
+            if !scaling_cur_freq.exists() {
+                continue;
+            }
+
+            cpus.insert(
+                cpu_n,
+                CpuFreqPolicy {
+                    scaling_cur_freq,
+                    cpuinfo_min_freq: cpufreq.join("cpuinfo_min_freq"),
+                    cpuinfo_max_freq: cpufreq.join("cpuinfo_max_freq"),
+                },
+            );
+        }
+
+        if cpus.is_empty() {
+            return Err(ah::anyhow!("No CPUs with cpufreq found"));
+        }
+
+        Ok(CpuFreq { cpus })
+    }
+}
@@ -7,6 +7,7 @@ macro_rules! debug {
 }
 
 #[cfg(not(feature = "debug"))]
+#[allow(unused_macros)]
 macro_rules! debug {
     ($($arg:tt)*) => {};
 }
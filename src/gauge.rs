@@ -0,0 +1,73 @@
+/// The partial blocks of a gauge cell, from one eighth full to seven.
+const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// The levels of a sparkline, from lowest to highest.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Clone, Copy)]
+pub enum GaugeStyle {
+    Blocks,
+    Ascii,
+}
+
+impl GaugeStyle {
+    pub fn from_str(s: &str) -> Option<GaugeStyle> {
+        match s {
+            "blocks" | "block" | "unicode" => Some(GaugeStyle::Blocks),
+            "ascii" => Some(GaugeStyle::Ascii),
+            _ => None,
+        }
+    }
+}
+
+/// How a sparkline is scaled: across the lowest and highest of its values,
+/// or from the gauge floor to each sensor's critical temperature.
+#[derive(Clone, Copy)]
+pub enum SparkScale {
+    Range,
+    Critical,
+}
+
+impl SparkScale {
+    pub fn from_str(s: &str) -> Option<SparkScale> {
+        match s {
+            "range" | "minmax" => Some(SparkScale::Range),
+            "crit" | "critical" => Some(SparkScale::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// The sparkline character of a fraction from 0 to 1.
+pub fn spark(fraction: f64) -> char {
+    SPARKS[(fraction.clamp(0.0, 1.0) * (SPARKS.len() - 1) as f64).round() as usize]
+}
+
+/// Renders a fraction from 0 to 1 as a bar that's always width cells wide.
+/// Blocks fill a cell an eighth at a time, ASCII only whole cells.
+pub fn render(fraction: f64, width: usize, style: GaugeStyle) -> String {
+    let filled = fraction.clamp(0.0, 1.0) * width as f64;
+
+    match style {
+        GaugeStyle::Blocks => {
+            let mut eighths = (filled * 8.0).round() as usize;
+            let mut gauge = String::new();
+
+            for _ in 0..width {
+                match eighths {
+                    0 => gauge.push(' '),
+                    1..=7 => gauge.push(EIGHTHS[eighths - 1]),
+                    _ => gauge.push('█'),
+                }
+
+                eighths = eighths.saturating_sub(8);
+            }
+
+            gauge
+        }
+        GaugeStyle::Ascii => {
+            let full = filled.round() as usize;
+            "#".repeat(full) + &".".repeat(width - full)
+        }
+    }
+}
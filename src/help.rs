@@ -28,44 +28,209 @@ Produces this output:
         Core 5: 53.32°C | Core 6: 53.32°C
         ---------------------------------
 
+Templates:
+
+    --format (-F) TEMPLATE
+        An alternative to long sequences of -s and segments. Each
+        {PLACEHOLDER} in TEMPLATE is replaced with a segment, and
+        everything else is printed as is, so that
+
+            --format '{avg:.1,g} [{min}/{max}] P:{package}'
+
+        prints the same as
+
+            -av -s ' [' -tm -s '/' -tx -s '] P:' -tp
+
+        except that the average is printed with precision 1 and the
+        glyph, and the other segments with the settings as they were.
+
+        Placeholders are the long names of segments without the dashes,
+        e.g. {avg}, {temp-package}, {zone-temp}, and the aliases {min},
+        {max}, {package}, {crit}, {alarm}, {cores}, {sockets}, {zone},
+        {nl}. Arguments follow the name, e.g. {temp 0 1 2}, {hwmon nvme}.
+
+        Options follow a colon, separated by comas, and only apply to
+        their placeholder. Every setting is back to what it was before
+        the placeholder once it's printed:
+            .N               Precision, see --precision
+            c, f, k, ...     Target unit, see --target-unit
+            khz, mhz, ghz    Frequency unit, see --freq-unit
+            g, glyph         Print the glyph, see --use-glyph
+            nog, noglyph     Don't print the glyph
+            floor, ceil ...  Rounding mode, see --rounding
+            <N, >N, ^N       Width N, aligned left, right or center,
+                             see --width and --align
+
+        Arguments can have colons of their own, e.g. {temp 1:3} or
+        {zone acpitz:1}, in which case the options follow the last colon,
+        as in {temp 1:3:.1}.
+
+        Use {{ and }} for literal braces. Templates can be mixed with
+        other segments, and the settings in effect before the template
+        apply to its placeholders.
+
+
+Output Modes:
+
+    --watch (-wa) INTERVAL [COUNT]
+        Prints the segments every INTERVAL milliseconds, one line per
+        update, forever, or COUNT times if given. For bars that read
+        lines from a long-lived process, like polybar with tail = true,
+        or lemonbar. Sensors are only discovered once.
+
+    --live (-lv) INTERVAL [COUNT]
+        Like --watch, but redraws the output in place on a terminal
+        rather than printing it again below, as a small live dashboard.
+        Output with more or fewer lines than before is redrawn cleanly.
+        Ctrl-C stops it, and leaves the last update on the terminal.
+
+    --alt-screen (-as) BOOL (default: false)
+        Draws --live on the alternate screen, like a full screen program,
+        which is left on Ctrl-C. Useful for output taller than the
+        terminal, which can't be redrawn in place.
+
+    --i3bar (-i3) [INTERVAL [COUNT]] (default: 1000)
+        Speaks the i3bar protocol, which swaybar also speaks, instead of
+        printing once. The segments are evaluated into a block every
+        INTERVAL milliseconds, forever, or COUNT times if given.
+
+        The block is urgent while the critical alarm of any core is
+        set. Unless --block-color is given, the block is colored by the
+        band of the hottest core, like --auto-color and --band-colors
+        color temperatures, or unless --auto-color is given, yellow from
+        80% and red from 95% of its critical temperature.
+        With --markup pango, the block is marked up with Pango.
+
+    --short-text (-st)
+        Segments after this are evaluated into the short_text of the
+        block, which i3bar shows when it runs out of space. Segments
+        before it make up the full_text. The short text starts out with
+        the --markup of the full text.
+
+    --block-name (-bn) NAME [INSTANCE] (default: coretempf)
+        Sets the name and instance of the block, which i3bar passes
+        along with click events.
+
+    --block-color (-bk) COLOR (default: auto)
+        Sets the color of the block (see --fg), or auto.
+
+    --waybar (-wb) [INTERVAL [COUNT]]
+        Prints the JSON of a Waybar custom module, for return-type json.
+        Prints once, or every INTERVAL milliseconds if given, forever,
+        or COUNT times if given.
+
+        The class and alt are normal, warm, or critical, by the band of
+        the hottest core (see --auto-color), or unless --auto-color is
+        given, warm from 80% and critical from 95% of its critical
+        temperature. The percentage is how close it is to its critical
+        temperature.
+
+    --tooltip (-tt)
+        Segments after this are evaluated into the tooltip of the
+        module, such as a table of every core. Segments before it
+        make up the text.
+
+        Both are escaped as Pango markup, which is what Waybar parses
+        them as, unless another --markup is given. The tooltip starts
+        out with the --markup of the text.
+
+
 Standalone Segments (no arguments):
 
     --glyph   (-g)
-        Print a temperature glyph for the current base unit.
+        Print a temperature glyph for the current target unit, or
+        the base unit if no target unit is set, the same glyph that
+        --use-glyph prints after temperatures.
         The default base unit is celcius, so the default glyph 
         is °C, but could also be: °F  °K 
 
 
-    --avg (-av)
-        The current average core temperature.
+    --avg (-av) [SOCKET]
+        The current average core temperature, of all sockets, or only
+        of SOCKET if given.
+
+
+    --median (-md) [SOCKET]
+        The current median core temperature (see --avg for SOCKET).
 
 
-    --median (-md)
-        The current median core temperature.
+    --temp-min (-tm) [SOCKET]
+        The lowest current core temperature (see --avg for SOCKET).
 
 
-    --min (-m)
-        The lowest current core temperature.
+    --temp-max (-tx) [SOCKET]
+        The highest current core temperature (see --avg for SOCKET).
 
 
-    --max (-mx)
-        The highest current core temperature.
+    --temp-package (-tp) [SOCKET]
+        The current package temperature of the first socket, or of
+        SOCKET if given.
 
 
-    --package (-pk)
-        The current package temperature.
+    --reset (-rs)
+        Resets colors and emphasis (see --fg, --bold) to the terminal's
+        defaults.
 
 
     --newline (-nl | -cr | -\\n)
         Prints a newline character.
 
 
-    --core-count (-cc)
-        Prints the total number of cores.
+    --core-count (-cc) [SOCKET]
+        Prints the total number of cores (see --avg for SOCKET).
+
+
+    --socket-count (-sc)
+        Prints the number of sockets (physical CPU packages).
+
+
+    --freq-avg (-qa)
+    --freq-median (-qd)
+    --freq-min (-qm)
+    --freq-max (-qx)
+        The average, median, lowest, or highest current frequency of all
+        logical CPUs, in the unit set by --freq-unit.
+
+
+    --usage (-u) [CPUS...]
+    --usage-user (-uu) [CPUS...]
+    --usage-system (-us) [CPUS...]
+    --usage-iowait (-uw) [CPUS...]
+    --usage-steal (-ut) [CPUS...]
+        The percentage of CPU time spent busy, or in user, system, iowait,
+        or steal time, of all CPUs combined, or of each of CPUS if given
+        (see --freq for CPUS). User includes nice time, and system includes
+        interrupt handling.
+
+        Usage is measured between two samples of /proc/stat, taken
+        --usage-interval apart. All usage segments in a sequence share the
+        same two samples. With -ug, a % sign is printed after the value.
+
+
+    --fan-fastest (-ff) [CHIP]
+    --fan-slowest (-fs) [CHIP]
+        The speed in RPM of the fastest or slowest fan, of all hwmon chips,
+        or only of CHIP if given (see --hwmon). Fans reporting 0 RPM are
+        ignored, as they're usually unconnected headers.
 
 
 Parameterized Segments (one or more arguments):
 
+    --sysfs-root (-sr) PATH (default: /sys)
+        Read sensors from PATH instead of /sys, e.g. a copy of another
+        machine's sysfs tree, or the host's /sys bind-mounted into a
+        container. Unlike other segments, its position doesn't matter.
+
+        The CORETEMPF_SYSFS_ROOT environment variable is used if this
+        argument isn't given.
+
+
+    --procfs-root (-pr) PATH (default: /proc)
+        Like --sysfs-root, for what is read from /proc, which is only
+        /proc/stat for CPU usage. The environment variable for it is
+        CORETEMPF_PROCFS_ROOT.
+
+
     --base-unit (-bu) UNIT (default: celcius)
         Sets the base unit to UNIT, which can be:
 
@@ -81,12 +246,163 @@ Parameterized Segments (one or more arguments):
         For Farenheit: °F, f, F, fahrenheit, Fahrenheit
         For Kelvin: °K, k, K, kelvin, Kelvin
 
-        If no target unit is set, no conversion is performed.
+        If no target unit is set, no conversion is performed, and the
+        value none (or off) unsets the target unit.
 
         If a target unit is set, then any temperature values will
         be converted from the base unit to the target unit.
 
 
+    --freq-unit (-qu) UNIT (default: MHz)
+        Sets the unit frequencies are printed in, which can be:
+
+        For Kilohertz: khz, kHz, KHz, k
+        For Megahertz: mhz, MHz, m, M
+        For Gigahertz: ghz, GHz, g, G
+
+        kHz and MHz are printed as whole numbers, GHz with 2 decimals.
+
+
+    --usage-interval (-ui) MILLISECONDS (default: 200)
+        Sets how far apart the two /proc/stat samples CPU usage is computed
+        from are taken. Since the samples are taken by the first usage
+        segment, this has to come before it to have an effect.
+
+
+    --precision (-p) N (default: default)
+        Sets the number of decimal places following numeric segments are
+        printed with, temperatures, frequencies and percentages alike. Like
+        --use-glyph, it can be changed in between segments, e.g.
+            -p 0 -t all -s ' avg ' -p 1 -av
+
+        The value default restores each segment's own precision, which is
+        2 for temperatures, 1 for percentages, and depends on the unit for
+        frequencies (see --freq-unit).
+
+
+    --rounding (-r) MODE (default: default)
+        Sets how following numeric segments are rounded to the number of
+        decimal places they're printed with (see --precision), after any
+        unit conversion. MODE can be:
+
+        floor, down          Towards negative infinity
+        ceil, up             Towards positive infinity
+        nearest, round       To nearest, halves away from zero
+        half-even, even      To nearest, halves to the even neighbour
+        truncate, trunc      Towards zero
+
+        The value default leaves rounding to the formatter, which rounds
+        to nearest on the exact binary value, so 38.85 may become 38.9.
+
+
+    --fg (-fg) COLOR
+    --bg (-bg) COLOR
+        Sets the foreground or background color of everything that
+        follows, until it's set again or reset with --reset. COLOR can be:
+
+        A name: black, red, green, yellow, blue, magenta, cyan, white,
+                or one of those prefixed with bright-, e.g. bright-red
+        A 256 color palette index: 0 to 255
+        A truecolor hex code: #rrggbb
+        default, to go back to the terminal's default color
+
+        The output always ends with a reset if any color or emphasis was
+        set, so it doesn't bleed into whatever is printed next.
+
+
+    --bold (-b) BOOL
+    --underline (-ul) BOOL
+        Turns bold or underlined text on or off for everything that follows.
+
+
+    --color (-co) WHEN (default: auto)
+        Whether colors and emphasis are printed at all, which can be
+        always, never, or auto. With auto, they are only printed when
+        stdout is a terminal, and NO_COLOR isn't set. Status bars usually
+        don't run coretempf in a terminal, so they need always, unless
+        --markup is given, which is always printed with auto.
+
+
+    --markup (-mk) DIALECT (default: ansi)
+        Writes colors and emphasis as the markup of a status bar instead
+        of terminal escape sequences, and escapes the text of --strings
+        so the bar doesn't interpret it. DIALECT can be:
+
+        ansi        Terminal escape sequences
+        pango       <span> markup, for i3bar, swaybar and waybar
+        tmux        #[fg=...] style, for status-left/status-right
+        lemonbar    %{F...} tags
+        polybar     %{F...} tags, the same as lemonbar
+        xmobar      <fc=...> tags
+
+        lemonbar and polybar have no bold text, and xmobar has neither
+        bold nor underlined text, nor a background without a foreground.
+
+
+    --auto-color (-ac) WARM HOT
+    --auto-color (-ac) crit [WARM HOT] (default: 80 95)
+    --auto-color (-ac) off (default)
+        Automatically colors following temperature segments by band:
+        normal below WARM, warm from WARM, and hot from HOT.
+
+        Given numbers, WARM and HOT are temperatures in the unit the
+        temperature is printed in. Given crit, they're percentages of
+        the sensor's critical temperature, e.g. the core's for --temp,
+        the package's for --avg, the critical trip point for --zone-temp.
+        Segments whose sensor has no critical temperature aren't colored.
+
+
+    --band-colors (-bc) NORMAL [WARM [HOT]] (default: default yellow red)
+        Sets the colors --auto-color uses for each band (see --fg).
+
+
+    --trend-glyphs (-tg) RISING [FALLING [STEADY]] (default: ↑ ↓ →)
+        Sets what --trend prints.
+
+
+    --trend-threshold (-th) RATE (default: 0.1)
+        Sets how many degrees per second --trend has to change by to be
+        rising or falling rather than steady.
+
+
+    --width (-w) N (default: 0)
+        Pads every following value, e.g. temperatures, frequencies,
+        percentages, counts and gauges, to at least N characters, so
+        that tables stay aligned when a value gets wider. Each value of
+        a list is padded on its own. Values are never cut off. Padding
+        comes before coloring, so colors and markup don't count.
+
+
+    --align (-al) ALIGN (default: right)
+        Sets where values sit within --width, which can be left, right,
+        or center.
+
+
+    --fill-char (-fc) CHAR (default: space)
+        Sets the character values are padded with to --width.
+
+
+    --gauge-width (-gw) N (default: 10)
+        Sets how many characters wide --gauge is.
+
+
+    --gauge-floor (-gf) TEMP (default: 0)
+        Sets the temperature at which --gauge is empty, in the unit the
+        temperature is printed in. It's full at the critical temperature.
+        Also the bottom of --sparkline with --spark-scale crit.
+
+
+    --spark-scale (-ss) SCALE (default: range)
+        Sets how --sparkline is scaled, which can be range, from the
+        coldest to the hottest of its cores, or crit, from --gauge-floor
+        to each core's critical temperature.
+
+
+    --gauge-style (-gs) STYLE (default: blocks)
+        Sets what --gauge is drawn with, which can be blocks, which fill
+        a character an eighth at a time, or ascii, with # and .
+
+
     --use-glyph (-ug) BOOL (default: false)
         If BOOL is true, then a temperature glyph will be printed
         alongside any temperature value that is printed. The glyph
@@ -105,6 +421,8 @@ Parameterized Segments (one or more arguments):
         E.g. whenever 53.32 is printed, if a temperature, will be
              printed as 53.32°C instead.
 
+        Frequencies are likewise printed with their unit, e.g. 4.30GHz.
+
     
     --temp (-t) CORES...
         Prints the current temperature of the cores specified by
@@ -124,6 +442,17 @@ Parameterized Segments (one or more arguments):
             Input: -s 'Core 5 = ' -t 4
             Output: Core 5 = 53.32
 
+        On machines with more than one socket, a core number alone refers
+        to the first socket. Cores on other sockets are given as SOCKET:CORE,
+        and SOCKET:all selects every core on one socket, e.g.
+            -t 1:3       Core 3 of socket 1
+            -t 0:all     Every core of socket 0
+
+        A core can also be given as cpuN, the core logical CPU N runs on.
+        That only works with coretemp, as k10temp and zenpower report one
+        temperature per CCD rather than per core. CPUs that don't exist
+        print N/A, like cores that don't exist do.
+
 
     --core-critical (-cC) CORES...
         Prints the critical temperature value for the given cores (see --temp)
@@ -132,12 +461,192 @@ Parameterized Segments (one or more arguments):
         Typicaly the same across all cores, between 90°C and 100°C
     
 
+    --zone-temp (-zt) ZONES...
+        Prints the current temperature of ACPI/device tree thermal zones,
+        from /sys/class/thermal, for laptops and ARM boards that have no
+        CPU hwmon driver. Zones are given by type, e.g. x86_pkg_temp,
+        cpu-thermal, acpitz, or by directory name, e.g. thermal_zone3.
+
+        When several zones share a type, TYPE:N selects the Nth of them,
+        counting from 0, so acpitz is the same as acpitz:0.
+
+        Unit conversion and glyphs apply as they do to core temperatures.
+
+
+    --zone-passive (-zp) ZONES...
+    --zone-hot (-zh) ZONES...
+    --zone-critical (-zc) ZONES...
+        Prints the passive, hot, or critical trip point of the given zones
+        (see --zone-temp). Prints N/A for zones without that trip point.
+
+
+    --hwmon (-hw) CHIP [LABELS...]
+        Prints the current temperature of any hwmon chip's sensors, such
+        as Super I/O chips (nct6798, it87), acpitz, nvme, or iwlwifi.
+
+        CHIP is the chip's name, as in /sys/class/hwmon/hwmon*/name, or the
+        directory name, e.g. hwmon3. When several chips share a name,
+        NAME:N selects the Nth of them, counting from 0.
+
+        LABELS are matched against tempN_label, ignoring case, and tempN
+        or just N can be given for sensors without a label. If no label is
+        given, the chip's first temperature sensor is printed, and if all
+        or * is given, all of them are.
+            Input: -hw nct6798 SYSTIN CPUTIN -s ' ' -hw acpitz
+            Output: 33.00, 41.50 27.80
+
+
+    --fan (-f) CHIP [LABELS...]
+    --fan-min (-fm) CHIP [LABELS...]
+    --fan-max (-fx) CHIP [LABELS...]
+    --fan-target (-ft) CHIP [LABELS...]
+        Prints the current, minimum, maximum, or target speed in RPM of
+        a hwmon chip's fans, from fanN_input, fanN_min, fanN_max, and
+        fanN_target. CHIP and LABELS work like they do for --hwmon, with
+        fanN_label, fanN or N in place of the temperature sensor's.
+            Input: -f nct6798 all -s ' RPM'
+            Output: 1200, 850, 0 RPM
+
+
+    --fan-pwm (-fp) CHIP [LABELS...]
+        Prints the duty cycle of a chip's PWM fan outputs as a percentage,
+        from pwmN. PWM outputs have no labels, so use pwmN, N, or all.
+
+
+    --drive (-dt) DRIVE [LABELS...]
+    --drive-min (-dm) DRIVE [LABELS...]
+    --drive-max (-dx) DRIVE [LABELS...]
+    --drive-critical (-dC) DRIVE [LABELS...]
+        Prints the current, minimum, maximum, or critical temperature of a
+        drive, from the nvme hwmon chip for NVMe drives, or the drivetemp
+        chip for SATA disks (which needs the drivetemp module loaded).
+
+        DRIVE is the block device name, e.g. nvme0n1 or sda, or the nvme
+        controller name, e.g. nvme0. LABELS work like they do for --hwmon,
+        e.g. Composite, 'Sensor 1', 'Sensor 2', and default to the drive's
+        first sensor, which for NVMe drives is Composite.
+            Input: -s 'nvme ' -dt nvme0n1 -s ' sda ' -dt sda
+            Output: nvme 38.85 sda 35.00
+
+
+    --freq (-q) CPUS...
+        Prints the current frequency of the given logical CPUs, from
+        cpuN/cpufreq/scaling_cur_freq. CPUS are the numbers of the CPUs
+        as in /sys/devices/system/cpu, optionally prefixed with cpu, or
+        all or * for every CPU. Like --temp, multiple CPUs are separated
+        with a coma and space.
+            Input: -qu ghz -q 0 1 2
+            Output: 4.30, 0.80, 3.60
+
+
+    --freq-hw-min (-ql) CPUS...
+    --freq-hw-max (-qh) CPUS...
+        Prints the lowest or highest frequency the given CPUs (see --freq)
+        support, from cpuinfo_min_freq and cpuinfo_max_freq.
+
+
+    --core-cpus (-cs) CORES...
+        Prints the logical CPUs that run on the given cores (see --temp),
+        separated by spaces, e.g. the hyperthread siblings of a core.
+            Input: -s 'Core 1 runs ' -cs 1
+            Output: Core 1 runs 1 7
+
+
     --core-alarm (-ca) CORES...
         Prints the critical alarm value for the given cores (see --temp) for 
         acceptable values of CORES...
 
         This value indicates whether or not the core is considered to be at a
-        critical temperature. The output can be true or false (per core).
+        critical temperature. The output can be true or false (per core),
+        labeled by its position, e.g. Core 1: false, or by SOCKET:CORE,
+        e.g. Core 1:3: false, on machines with more than one socket.
+
+
+    --gauge (-ga) CORE
+    --gauge (-ga) avg|max|package [SOCKET]
+        Prints a temperature as a gauge, between --gauge-floor and the
+        sensor's critical temperature. With -gf 30 -gw 8, a core at 80°C
+        with a critical temperature of 100°C prints
+
+            █████▊  
+
+        CORE is a core like those of --temp. avg and max cover every
+        socket unless one is given, and package is that of the first
+        socket unless one is given. Prints N/A if the sensor has no
+        critical temperature. Follows --auto-color like temperatures.
+
+
+    --moving-avg (-ma) SECONDS [SOURCE [SOCKET]]
+    --window-min (-wm) SECONDS [SOURCE [SOCKET]]
+    --window-max (-wx) SECONDS [SOURCE [SOCKET]]
+        Prints the average, lowest, or highest temperature over the last
+        SECONDS, which smooths out the jumps between readings. SOURCE is
+        a core, or avg (the default), max or package, like --gauge.
+
+        Readings are kept while coretempf runs, with --watch, --live,
+        --i3bar or --waybar, once per update, for up to an hour. A single
+        run only has its one reading.
+
+
+    --ema (-em) ALPHA [SOURCE [SOCKET]]
+        Prints the exponential moving average of the temperature, where
+        each update weighs the new reading by ALPHA, from 0 to 1, and the
+        average so far by 1 - ALPHA. Lower is smoother, but slower to
+        follow. SOURCE is like --moving-avg.
+
+
+    --peak-max (-px) [CORES...]
+    --peak-max (-px) package [SOCKET]
+    --peak-min (-pn) [CORES...]
+    --peak-min (-pn) package [SOCKET]
+        Prints the highest or lowest temperature since boot, of the given
+        cores (see --temp), or of the package of the first socket unless
+        one is given, which is also the default. Follows --auto-color
+        like temperatures.
+
+        Every run with XDG_RUNTIME_DIR set updates the peaks of every
+        core and package in $XDG_RUNTIME_DIR/coretempf/state, and they
+        start over when the machine reboots. Without it, only the
+        readings of the current run count.
+
+    --reset-peaks (-rp)
+        Forgets the peaks so far, starting over from the current
+        readings. Prints nothing. With --watch and the other modes that
+        update repeatedly, only on the first update.
+
+
+    --trend (-tr) [SOURCE [SOCKET]]
+        Prints whether the temperature is rising, falling, or steady
+        since the previous reading (see --trend-glyphs). SOURCE is like
+        --moving-avg.
+
+    --rate (-rt) [SOURCE [SOCKET]]
+        Prints how fast the temperature changed since the previous
+        reading, in degrees per second of the unit it's printed in.
+        With --use-glyph, e.g. 0.50°C/s.
+
+        Both keep the reading in $XDG_RUNTIME_DIR/coretempf/state, so
+        the previous reading can be that of the previous run, as most
+        bars run coretempf once per update. Under --watch, --live and
+        the bar modes, the previous reading is also kept in memory.
+        They print N/A if the previous reading is over 5 minutes old,
+        or, for a one-shot run without XDG_RUNTIME_DIR, always.
+
+
+    --rule (-ru) WIDTH [STRING]
+        Repeats STRING, or - if not given, to exactly WIDTH characters,
+        e.g. for the borders of a table. -ru 9 '=-' prints =-=-=-=-=
+
+
+    --sparkline (-sp) CORES...
+        Prints the given cores (see --temp) as a sparkline, with one
+        character per core, e.g. -sp all prints
+
+            ▂▃▃▅█▂
+
+        A compact alternative to --temp for narrow bars. Cores that
+        can't be read, or with --spark-scale crit have no critical
+        temperature, are a space. Follows --auto-color per core.
 ";
 
 pub fn exit_with_usage(code: i32) {
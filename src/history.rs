@@ -0,0 +1,113 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Samples older than this are dropped, which makes it the longest window.
+const RETENTION: Duration = Duration::from_secs(3600);
+
+/// The most samples kept per sensor, however short the interval.
+const CAPACITY: usize = 4096;
+
+#[derive(Default)]
+struct Series {
+    samples: VecDeque<(Instant, f64)>,
+    recorded_tick: Option<u64>,
+    // Keyed by the bits of alpha, with the tick each was last updated on.
+    emas: HashMap<u64, (u64, f64)>,
+}
+
+/// Readings of sensors over the ticks of a long-lived process, like --watch,
+/// for smoothing out the jumps between readings. Sensors are keyed by name,
+/// e.g. "core 0:3" or "package 0".
+pub struct History {
+    tick: Cell<u64>,
+    series: RefCell<HashMap<String, Series>>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            tick: Cell::new(0),
+            series: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn tick(&self) {
+        self.tick.set(self.tick.get() + 1);
+    }
+
+    /// Records a reading, only once per tick however many segments read the
+    /// same sensor.
+    pub fn record(&self, key: &str, value: f64) {
+        let mut series = self.series.borrow_mut();
+        let series = series.entry(key.to_string()).or_default();
+
+        if series.recorded_tick == Some(self.tick.get()) {
+            return;
+        }
+
+        let now = Instant::now();
+
+        while series.samples.front().is_some_and(|(at, _)| {
+            now.duration_since(*at) > RETENTION || series.samples.len() >= CAPACITY
+        }) {
+            series.samples.pop_front();
+        }
+
+        series.samples.push_back((now, value));
+        series.recorded_tick = Some(self.tick.get());
+    }
+
+    /// The readings within the window before the newest, which is always
+    /// included, even with a window of 0.
+    fn window(&self, key: &str, window: Duration) -> Vec<f64> {
+        let series = self.series.borrow();
+
+        let samples = match series.get(key) {
+            Some(series) => &series.samples,
+            None => return Vec::new(),
+        };
+
+        let newest = match samples.back() {
+            Some((at, _)) => *at,
+            None => return Vec::new(),
+        };
+
+        samples
+            .iter()
+            .filter(|(at, _)| newest.duration_since(*at) <= window)
+            .map(|(_, value)| *value)
+            .collect()
+    }
+
+    pub fn get_average(&self, key: &str, window: Duration) -> Option<f64> {
+        let values = self.window(key, window);
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    pub fn get_min(&self, key: &str, window: Duration) -> Option<f64> {
+        self.window(key, window).into_iter().reduce(f64::min)
+    }
+
+    pub fn get_max(&self, key: &str, window: Duration) -> Option<f64> {
+        self.window(key, window).into_iter().reduce(f64::max)
+    }
+
+    /// The exponential moving average of the readings, updated once per
+    /// tick, and starting out at the first reading.
+    pub fn get_ema(&self, key: &str, alpha: f64) -> Option<f64> {
+        let tick = self.tick.get();
+        let mut series = self.series.borrow_mut();
+        let series = series.get_mut(key)?;
+        let latest = series.samples.back()?.1;
+
+        let ema = match series.emas.get(&alpha.to_bits()) {
+            Some((updated, ema)) if *updated == tick => *ema,
+            Some((_, ema)) => alpha * latest + (1.0 - alpha) * ema,
+            None => latest,
+        };
+
+        series.emas.insert(alpha.to_bits(), (tick, ema));
+        Some(ema)
+    }
+}
@@ -0,0 +1,322 @@
+use anyhow as ah;
+use std::fs;
+
+use std::path::{Path, PathBuf};
+
+use crate::sysfs;
+
+/// One numbered channel of a chip, e.g. temp3 or fan1, which has a
+/// {kind}{index}_input file, and optionally a {kind}{index}_label file.
+/// PWM outputs are the exception, their value is in the bare pwm{index}.
+struct Channel {
+    kind: String,
+    index: u64,
+    label: Option<String>,
+}
+
+struct HwmonChip {
+    name: String,
+    dir_name: String,
+    path: PathBuf,
+    channels: Vec<Channel>,
+    // Names of the drive behind the chip's device symlink, e.g. nvme0 and
+    // nvme0n1, or sda. Only looked for on the nvme and drivetemp chips.
+    drives: Vec<String>,
+}
+
+impl HwmonChip {
+    /// Finds a channel of the given kind by its label (case insensitive), by
+    /// its attribute prefix, e.g. temp2, or by its index alone. With no label,
+    /// the channel with the lowest index is used, which is handy for chips
+    /// like acpitz that only have one unlabeled sensor.
+    fn channel(&self, kind: &str, label: Option<&str>) -> ah::Result<&Channel> {
+        let mut channels = self.channels.iter().filter(|c| c.kind == kind);
+
+        let channel = match label {
+            Some(label) => channels.find(|c| {
+                c.label
+                    .as_ref()
+                    .is_some_and(|l| l.eq_ignore_ascii_case(label))
+                    || format!("{}{}", c.kind, c.index) == label
+                    || c.index.to_string() == label
+            }),
+            None => channels.min_by_key(|c| c.index),
+        };
+
+        channel.ok_or(ah::anyhow!(
+            "Chip {} has no {} sensor {}",
+            self.name,
+            kind,
+            label.unwrap_or_default()
+        ))
+    }
+
+    /// Reads {kind}{index}_{attr}, or {kind}{index} if attr is empty.
+    fn read_attr(&self, channel: &Channel, attr: &str) -> ah::Result<u64> {
+        let attr_path = match attr {
+            "" => self.path.join(format!("{}{}", channel.kind, channel.index)),
+            attr => self
+                .path
+                .join(format!("{}{}_{}", channel.kind, channel.index, attr)),
+        };
+
+        let value = fs::read_to_string(attr_path)?;
+        Ok(value.trim().parse::<u64>()?)
+    }
+}
+
+/// Finds the names a drive chip's device goes by. For nvme, the device is
+/// the controller (nvme0), with its namespaces (nvme0n1) as subdirectories.
+/// For drivetemp, the device is a SCSI device whose block subdirectory holds
+/// the block device (sda).
+fn drive_names(chip_name: &str, chip_path: &Path) -> Vec<String> {
+    let device = match fs::canonicalize(chip_path.join("device")) {
+        Ok(device) => device,
+        Err(_) => return Vec::new(),
+    };
+
+    let list_dir = |path: &Path| -> Vec<String> {
+        let mut names: Vec<String> = match fs::read_dir(path) {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str().map(String::from))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        names.sort();
+        names
+    };
+
+    match chip_name {
+        "nvme" => {
+            let controller = match device.file_name().and_then(|name| name.to_str()) {
+                Some(controller) => controller.to_string(),
+                None => return Vec::new(),
+            };
+
+            let namespaces: Vec<String> = list_dir(&device)
+                .iter()
+                .filter_map(|name| nvme_namespace(&controller, name))
+                .collect();
+
+            let mut drives = vec![controller];
+            drives.extend(namespaces);
+            drives
+        }
+        "drivetemp" => list_dir(&device.join("block")),
+        _ => Vec::new(),
+    }
+}
+
+/// The block device name of a namespace subdirectory of an nvme controller.
+/// That's the subdirectory itself, e.g. nvme0n1, except with native NVMe
+/// multipathing, where the controller holds a path to the namespace, e.g.
+/// nvme0c1n1 (subsystem 0, controller 1, namespace 1), for nvme0n1.
+fn nvme_namespace(controller: &str, name: &str) -> Option<String> {
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    let (head, namespace) = name.rsplit_once('n')?;
+
+    if !is_number(namespace) {
+        return None;
+    }
+
+    if head == controller {
+        return Some(name.to_string());
+    }
+
+    let (subsystem, path_controller) = head.strip_prefix("nvme")?.split_once('c')?;
+
+    (is_number(subsystem) && format!("nvme{}", path_controller) == controller)
+        .then(|| format!("nvme{}n{}", subsystem, namespace))
+}
+
+/// Every chip in the hwmon class, regardless of driver, for reading
+/// sensors that aren't part of the CPU (Super I/O, acpitz, nvme, etc).
+pub struct Hwmon {
+    chips: Vec<HwmonChip>,
+}
+
+impl Hwmon {
+    /// Looks up a chip by its name (e.g. nct6798, it87, acpitz), or by its
+    /// directory name (e.g. hwmon3). When several chips share a name,
+    /// NAME:N picks the Nth of them, counting from 0.
+    fn chip(&self, chip: &str) -> ah::Result<&HwmonChip> {
+        let (name, nth) = match chip.rsplit_once(':') {
+            Some((name, nth)) => match nth.parse::<usize>() {
+                Ok(nth) => (name, nth),
+                Err(_) => (chip, 0),
+            },
+            None => (chip, 0),
+        };
+
+        self.chips
+            .iter()
+            .filter(|c| c.name == name)
+            .nth(nth)
+            .or_else(|| self.chips.iter().find(|c| c.dir_name == chip))
+            .ok_or(ah::anyhow!("Hwmon chip {} does not exist", chip))
+    }
+
+    /// Looks up the nvme or drivetemp chip of a drive by its block device
+    /// name (e.g. nvme0n1, sda), or by its nvme controller name (nvme0).
+    fn drive(&self, drive: &str) -> ah::Result<&HwmonChip> {
+        self.chips
+            .iter()
+            .find(|c| c.drives.iter().any(|d| d == drive))
+            .ok_or(ah::anyhow!("Drive {} has no temperature sensor", drive))
+    }
+
+    /// Reads {attr} of a drive's temp channel with the given label, e.g.
+    /// input, min, max, crit. Without a label, that's Composite for nvme
+    /// drives, and the only sensor drivetemp has.
+    pub fn get_drive_temp(&self, drive: &str, label: Option<&str>, attr: &str) -> ah::Result<u64> {
+        let chip = self.drive(drive)?;
+        let channel = chip.channel("temp", label)?;
+
+        chip.read_attr(channel, attr)
+    }
+
+    /// The channels of the given kind on a chip, as {kind}{index}, in order.
+    pub fn get_channels(&self, chip: &str, kind: &str) -> ah::Result<Vec<String>> {
+        Ok(self
+            .chip(chip)?
+            .channels
+            .iter()
+            .filter(|c| c.kind == kind)
+            .map(|c| format!("{}{}", c.kind, c.index))
+            .collect())
+    }
+
+    /// Reads {attr} of the temp channel with the given label, e.g. input,
+    /// crit, max. See HwmonChip::channel for how labels are matched.
+    pub fn get_temp(&self, chip: &str, label: Option<&str>, attr: &str) -> ah::Result<u64> {
+        let chip = self.chip(chip)?;
+        let channel = chip.channel("temp", label)?;
+
+        chip.read_attr(channel, attr)
+    }
+
+    /// Reads {attr} of the fan channel with the given label, which is one of
+    /// input, min, max, or target, all in RPM.
+    pub fn get_fan(&self, chip: &str, label: Option<&str>, attr: &str) -> ah::Result<u64> {
+        let chip = self.chip(chip)?;
+        let channel = chip.channel("fan", label)?;
+
+        chip.read_attr(channel, attr)
+    }
+
+    /// Reads the duty cycle of a PWM output as a percentage. The pwmN file
+    /// holds a value between 0 and 255.
+    pub fn get_pwm(&self, chip: &str, label: Option<&str>) -> ah::Result<u64> {
+        let chip = self.chip(chip)?;
+        let channel = chip.channel("pwm", label)?;
+
+        let duty = chip.read_attr(channel, "")?;
+        Ok((duty * 100 + 127) / 255)
+    }
+
+    /// Reads every fan on the given chip, or on all chips if None. Fans that
+    /// report 0 RPM are left out, since those are almost always headers
+    /// with nothing plugged into them.
+    fn read_fans(&self, chip: Option<&str>) -> ah::Result<Vec<u64>> {
+        let chips: Vec<&HwmonChip> = match chip {
+            Some(chip) => vec![self.chip(chip)?],
+            None => self.chips.iter().collect(),
+        };
+
+        let mut speeds = Vec::new();
+
+        for chip in chips {
+            for channel in chip.channels.iter().filter(|c| c.kind == "fan") {
+                match chip.read_attr(channel, "input") {
+                    Ok(0) | Err(_) => continue,
+                    Ok(speed) => speeds.push(speed),
+                }
+            }
+        }
+
+        if speeds.is_empty() {
+            return Err(ah::anyhow!("No spinning fans found"));
+        }
+
+        Ok(speeds)
+    }
+
+    pub fn get_fan_fastest(&self, chip: Option<&str>) -> ah::Result<u64> {
+        let speeds = self.read_fans(chip)?;
+        Ok(speeds.into_iter().max().unwrap_or(u64::MIN))
+    }
+
+    pub fn get_fan_slowest(&self, chip: Option<&str>) -> ah::Result<u64> {
+        let speeds = self.read_fans(chip)?;
+        Ok(speeds.into_iter().min().unwrap_or(u64::MAX))
+    }
+
+    pub fn try_new(sysfs_root: &Path) -> ah::Result<Hwmon> {
+        let mut chips = Vec::<HwmonChip>::new();
+
+        for hwmon_dir in sysfs::hwmon_dirs(sysfs_root)? {
+            let (name, dir_path) = (hwmon_dir.name, hwmon_dir.path);
+
+            let mut channels = Vec::<Channel>::new();
+
+            // Collect every {kind}{index}_input file, e.g. temp1_input or
+            // fan2_input, along with the label next to it if there is one,
+            // and every pwm{index} file.
+            for entry in fs::read_dir(&dir_path)?.flatten() {
+                let entry_name = entry.file_name();
+
+                let prefix = match entry_name.to_str() {
+                    Some(name) => match name.strip_suffix("_input") {
+                        Some(prefix) => prefix,
+                        None if name.starts_with("pwm") && !name.contains('_') => name,
+                        None => continue,
+                    },
+                    None => continue,
+                };
+
+                let digits_at = match prefix.find(|c: char| c.is_ascii_digit()) {
+                    Some(digits_at) if digits_at > 0 => digits_at,
+                    _ => continue,
+                };
+
+                let (kind, index) = prefix.split_at(digits_at);
+
+                let index = match index.parse::<u64>() {
+                    Ok(index) => index,
+                    Err(_) => continue,
+                };
+
+                let label = fs::read_to_string(dir_path.join(format!("{}_label", prefix)))
+                    .ok()
+                    .map(|label| label.trim().to_string());
+
+                channels.push(Channel {
+                    kind: kind.to_string(),
+                    index,
+                    label,
+                });
+            }
+
+            channels.sort_by(|a, b| (&a.kind, a.index).cmp(&(&b.kind, b.index)));
+
+            let drives = drive_names(&name, &dir_path);
+
+            chips.push(HwmonChip {
+                name,
+                dir_name: format!("hwmon{}", hwmon_dir.number),
+                path: dir_path,
+                channels,
+                drives,
+            });
+        }
+
+        if chips.is_empty() {
+            return Err(ah::anyhow!("No hwmon chips found"));
+        }
+
+        Ok(Hwmon { chips })
+    }
+}
@@ -0,0 +1,54 @@
+/// Where text sits within its --width, when it's narrower.
+#[derive(Clone, Copy)]
+pub enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+impl Align {
+    pub fn from_str(s: &str) -> Option<Align> {
+        match s {
+            "left" | "l" | "<" => Some(Align::Left),
+            "right" | "r" | ">" => Some(Align::Right),
+            "center" | "centre" | "c" | "^" => Some(Align::Center),
+            _ => None,
+        }
+    }
+}
+
+/// Pads text with fill to at least width characters. Text that's already
+/// as wide is left as is, rather than cut off. Both go through escape once
+/// padded, since the fill is user text, and the text can have glyphs and
+/// suffixes that mean something to markup, like % to lemonbar.
+pub fn pad(
+    text: String,
+    width: usize,
+    align: Align,
+    fill: char,
+    escape: impl Fn(&str) -> String,
+) -> String {
+    let len = text.chars().count();
+
+    if len >= width {
+        return escape(&text);
+    }
+
+    let missing = width - len;
+
+    let (left, right) = match align {
+        Align::Left => (0, missing),
+        Align::Right => (missing, 0),
+        Align::Center => (missing / 2, missing - missing / 2),
+    };
+
+    let fill = |n: usize| fill.to_string().repeat(n);
+
+    escape(&(fill(left) + &text + &fill(right)))
+}
+
+/// Repeats a string to exactly width characters, cutting the last
+/// repetition short if it doesn't fit.
+pub fn rule(string: &str, width: usize) -> String {
+    string.chars().cycle().take(width).collect()
+}
@@ -4,6 +4,46 @@ mod debug;
 mod temps;
 use temps::*;
 
+mod thermal;
+
+mod hwmon;
+use hwmon::Hwmon;
+
+mod cpufreq;
+
+mod usage;
+use usage::UsageKind;
+
+mod topology;
+
+mod sysfs;
+
+mod template;
+
+mod color;
+use color::{AutoColor, Band, Color, ColorMode};
+
+mod markup;
+use markup::{Dialect, Style};
+
+mod layout;
+use layout::Align;
+
+mod gauge;
+use gauge::{GaugeStyle, SparkScale};
+
+mod history;
+
+mod state;
+
+mod peaks;
+
+mod sensors;
+use sensors::Sensors;
+
+mod bar;
+use bar::{Bands, Block, WaybarModule};
+
 mod units;
 use units::*;
 
@@ -11,14 +51,48 @@ mod help;
 use help::exit_with_usage;
 
 use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow as ah;
 
+type IndexedArgs = Vec<(usize, String)>;
+
+/// A sequence of segments, as key and values pairs.
+type Segments = [(String, Vec<String>)];
+
+const SYSFS_ROOT: &str = "/sys";
+const SYSFS_ROOT_ENV: &str = "CORETEMPF_SYSFS_ROOT";
+
+const PROCFS_ROOT: &str = "/proc";
+const PROCFS_ROOT_ENV: &str = "CORETEMPF_PROCFS_ROOT";
+
+const SHORT_TEXT_KEYS: [&str; 2] = ["--short-text", "-st"];
+const TOOLTIP_KEYS: [&str; 2] = ["--tooltip", "-tt"];
+
+/// How old the previous reading of --trend and --rate can be.
+const STALE_READING: Duration = Duration::from_secs(300);
+
+/// How often --live checks for Ctrl-C while waiting for the next frame.
+const LIVE_POLL: Duration = Duration::from_millis(50);
+
+/// How often an i3bar block is updated, unless given.
+const I3BAR_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The default colors of the normal, warm and hot bands.
+const BAND_COLORS: [Color; 3] = [Color::Default, Color::Named(3), Color::Named(1)];
+
+/// How long CPU usage is measured over, when there's no previous sample.
+const USAGE_INTERVAL: Duration = Duration::from_millis(200);
+
 fn parse_args() -> Vec<(String, Vec<String>)> {
     let args: Vec<String> = std::env::args().collect();
-    let args_with_index: Vec<(usize, String)> = args.into_iter().enumerate().collect();
+    let args_with_index: IndexedArgs = args.into_iter().enumerate().collect();
 
-    let (key_args, val_args): (Vec<(usize, String)>, Vec<(usize, String)>) =
+    let (key_args, val_args): (IndexedArgs, IndexedArgs) =
         args_with_index.into_iter().partition(|(_, arg)| {
             (arg.starts_with("--")
                 && arg
@@ -59,73 +133,734 @@ fn parse_args() -> Vec<(String, Vec<String>)> {
     argument_pairs
 }
 
-fn process_segments(ct: &CoreTemp, argm: Vec<(String, Vec<String>)>) -> ah::Result<String> {
+/// The values of the last of the given keys. For options that apply to the
+/// whole run rather than to the segments after them.
+fn last_option<'a>(arguments: &'a Segments, keys: [&str; 2]) -> Option<&'a [String]> {
+    arguments
+        .iter()
+        .rev()
+        .find(|(key, _)| keys.contains(&key.as_str()))
+        .map(|(_, values)| values.as_slice())
+}
+
+/// The sysfs and procfs roots are needed before any segment is evaluated, so
+/// unlike other arguments their position doesn't matter. The last of the
+/// given keys wins over the environment, which wins over the default.
+fn fs_root(arguments: &Segments, keys: [&str; 2], env: &str, default: &str) -> PathBuf {
+    last_option(arguments, keys)
+        .and_then(|values| values.first())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os(env).map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(default))
+}
+
+/// Where values that outlive a run are kept, see State. Without a runtime
+/// directory, they only last as long as the process does.
+fn state_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| PathBuf::from(dir).join("coretempf").join("state"))
+}
+
+/// Replaces every --format TEMPLATE with the segments the template expands
+/// to, so that templates and ordinary segments can be mixed.
+fn expand_templates(
+    arguments: Vec<(String, Vec<String>)>,
+) -> ah::Result<Vec<(String, Vec<String>)>> {
+    let mut expanded = Vec::with_capacity(arguments.len());
+
+    for (key, values) in arguments {
+        if matches!(key.as_str(), "--format" | "-F") {
+            expanded.append(&mut template::expand(&values.join(" "))?);
+        } else {
+            expanded.push((key, values));
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Splits the sequence at the first of the given keys, for outputs that take
+/// a second sequence, such as the short text of an i3bar block.
+fn split_sequence<'a>(
+    arguments: &'a Segments,
+    keys: &[&str],
+) -> (&'a Segments, Option<&'a Segments>) {
+    match arguments
+        .iter()
+        .position(|(key, _)| keys.contains(&key.as_str()))
+    {
+        Some(i) => (&arguments[..i], Some(&arguments[i + 1..])),
+        None => (arguments, None),
+    }
+}
+
+/// Starts a second sequence with the --markup of the first, so that both are
+/// escaped for the dialect the bar parses them in, while the second sequence
+/// can still switch to another dialect itself.
+fn with_markup(markup: Option<&[String]>, segments: &Segments) -> Vec<(String, Vec<String>)> {
+    let mut seeded: Vec<(String, Vec<String>)> = markup
+        .map(|markup| ("--markup".to_string(), markup.to_vec()))
+        .into_iter()
+        .collect();
+
+    seeded.extend_from_slice(segments);
+    seeded
+}
+
+/// Resolves the CORES... values of -t, -cC, -ca and -sp. Each value is either a
+/// core, a SOCKET:CORE pair, all or * for every core on every socket,
+/// SOCKET:all for every core on one socket, or cpuN for the core that
+/// logical CPU N runs on.
+/// A CPU that isn't in the topology is None, and prints N/A, like a core
+/// that doesn't exist does.
+fn select_cores(sensors: &Sensors, values: &[String]) -> ah::Result<Vec<Option<CoreId>>> {
+    let ct = sensors.core_temp()?;
+    let default_socket = ct.get_default_socket();
+    let mut cores = Vec::new();
+
+    for value in values {
+        if matches!(value.as_str(), "all" | "*") {
+            cores.extend(ct.get_cores(None)?.into_iter().map(Some));
+            continue;
+        }
+
+        if let Some((socket, "all" | "*")) = value.split_once(':') {
+            if let Ok(socket) = socket.parse::<u64>() {
+                cores.extend(ct.get_cores(Some(socket))?.into_iter().map(Some));
+            }
+
+            continue;
+        }
+
+        if let Some(cpu) = value.strip_prefix("cpu") {
+            if let Ok(cpu) = cpu.parse::<u64>() {
+                let core = sensors
+                    .topology()
+                    .ok()
+                    .and_then(|topology| topology.get_core(cpu).ok());
+
+                // Only coretemp has a sensor per physical core, AMD's are per
+                // CCD, which can't be told apart from the CPU topology.
+                if let Some(core) = core.filter(|core| !ct.has_physical_cores(core.socket)) {
+                    return Err(ah::anyhow!(
+                        "CPU {} can't be mapped to a core sensor, as socket {} only has per-CCD sensors",
+                        cpu,
+                        core.socket
+                    ));
+                }
+
+                cores.push(core);
+            }
+
+            continue;
+        }
+
+        if let Some(core) = CoreId::from_str(value, default_socket) {
+            cores.push(Some(core));
+        }
+    }
+
+    Ok(cores)
+}
+
+/// Reads the SOURCE [SOCKET] values of --gauge and the history segments: a
+/// core, or the avg, max or package temperature of a socket, which default
+/// to every socket and the first socket. Returns a key that identifies the
+/// sensor, along with its temperature and critical temperature.
+fn read_source(
+    sensors: &Sensors,
+    source: &str,
+    socket: Option<&String>,
+) -> ah::Result<(String, Option<f64>, Option<u64>)> {
+    let ct = sensors.core_temp()?;
+    let socket = socket.and_then(|s| s.parse::<u64>().ok());
+    let socket_key = socket.map_or("all".to_string(), |s| s.to_string());
+
+    Ok(match source {
+        "avg" | "average" => (
+            format!("avg {}", socket_key),
+            Some(ct.get_average(socket)?),
+            ct.get_socket_critical(socket).ok(),
+        ),
+        "max" => (
+            format!("max {}", socket_key),
+            Some(ct.get_max(socket)? as f64),
+            ct.get_socket_critical(socket).ok(),
+        ),
+        "package" => {
+            let socket = socket.unwrap_or(ct.get_default_socket());
+            (
+                format!("package {}", socket),
+                Some(ct.get_package(socket)? as f64),
+                ct.get_package_critical(socket).ok(),
+            )
+        }
+        core => match CoreId::from_str(core, ct.get_default_socket()) {
+            Some(core) => (
+                format!("core {}", core),
+                ct.get_temp(core).ok().map(|temp| temp as f64),
+                ct.get_critical(core).ok(),
+            ),
+            None => return Err(ah::anyhow!("Invalid temperature source '{}'", core)),
+        },
+    })
+}
+
+/// The previous reading of a sensor, and how many seconds ago it was, from
+/// the state, which then keeps this reading for the next run. Readings
+/// older than STALE_READING say little about now, so they don't count.
+fn previous_reading(sensors: &Sensors, sensor: &str, temp: f64) -> Option<(f64, f64)> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs_f64();
+    let key = format!("reading {}", sensor);
+
+    let state = sensors.state();
+    let previous = state.get_previous(&key);
+    state.set(&key, format!("{} {}", now, temp));
+
+    let (then, previous) = previous?.split_once(' ').and_then(|(then, previous)| {
+        Some((then.parse::<f64>().ok()?, previous.parse::<f64>().ok()?))
+    })?;
+
+    let elapsed = now - then;
+    (elapsed > 0.0 && elapsed <= STALE_READING.as_secs_f64()).then_some((previous, elapsed))
+}
+
+/// Resolves the CPUS... values of the cpufreq segments. Each value is either
+/// a logical CPU number, cpuN, or all or * for every CPU in all_cpus.
+fn select_cpus(all_cpus: Vec<u64>, values: &[String]) -> Vec<u64> {
+    let mut cpus = Vec::new();
+
+    for value in values {
+        if matches!(value.as_str(), "all" | "*") {
+            cpus.extend(&all_cpus);
+            continue;
+        }
+
+        let cpu = value.strip_prefix("cpu").unwrap_or(value);
+
+        if let Ok(cpu) = cpu.parse::<u64>() {
+            cpus.push(cpu);
+        }
+    }
+
+    cpus
+}
+
+/// Resolves the LABELS... values of the hwmon segments. No labels selects the
+/// chip's first channel of that kind, and all or * selects all of them.
+fn select_channels(
+    hwmon: &Hwmon,
+    chip: &str,
+    kind: &str,
+    labels: &[String],
+) -> ah::Result<Vec<Option<String>>> {
+    if labels.is_empty() {
+        return Ok(vec![None]);
+    }
+
+    let mut channels = Vec::new();
+
+    for label in labels {
+        if matches!(label.as_str(), "all" | "*") {
+            channels.extend(hwmon.get_channels(chip, kind)?.into_iter().map(Some));
+        } else {
+            channels.push(Some(label.clone()));
+        }
+    }
+
+    Ok(channels)
+}
+
+fn process_segments(sensors: &Sensors, argm: &Segments) -> ah::Result<String> {
+    #[derive(Clone)]
     struct FormatSettings {
         pub include_glyph: bool,
         pub base_unit: Unit,
         pub target_unit: Option<Unit>,
+        pub freq_unit: FreqUnit,
+        pub usage_interval: Duration,
+        pub precision: Option<usize>,
+        pub rounding: Option<Rounding>,
+        pub color_mode: ColorMode,
+        pub dialect: Dialect,
+        pub style: Style,
+        pub auto_color: AutoColor,
+        pub band_colors: [Color; 3],
+        pub gauge_width: usize,
+        pub gauge_floor: f64,
+        pub gauge_style: GaugeStyle,
+        pub spark_scale: SparkScale,
+        pub width: usize,
+        pub align: Align,
+        pub fill: char,
+        pub trend_glyphs: [String; 3],
+        pub trend_threshold: f64,
     }
 
     let mut fmts = FormatSettings {
         include_glyph: false,
         base_unit: Unit::Celcius,
         target_unit: None,
+        freq_unit: FreqUnit::Megahertz,
+        usage_interval: USAGE_INTERVAL,
+        precision: None,
+        rounding: None,
+        color_mode: ColorMode::Auto,
+        dialect: Dialect::Ansi,
+        style: Style::default(),
+        auto_color: AutoColor::Off,
+        band_colors: BAND_COLORS,
+        gauge_width: 10,
+        gauge_floor: 0.0,
+        gauge_style: GaugeStyle::Blocks,
+        spark_scale: SparkScale::Range,
+        width: 0,
+        align: Align::Right,
+        fill: ' ',
+        trend_glyphs: ["↑".into(), "↓".into(), "→".into()],
+        trend_threshold: 0.1,
     };
 
+    impl FormatSettings {
+        fn colors(&self) -> bool {
+            self.color_mode.is_enabled(&self.dialect)
+        }
+
+        /// Pads a value to the current width, and escapes it for the
+        /// dialect. Values are padded before they're colored, so markup
+        /// doesn't count towards the width.
+        fn pad(&self, text: String) -> String {
+            layout::pad(text, self.width, self.align, self.fill, |fill| {
+                self.dialect.escape(fill)
+            })
+        }
+    }
+
+    /// Rounds and formats a value to the current precision, or to the
+    /// segment's own default precision if none is set.
+    fn print_number(value: f64, default_precision: usize, settings: &FormatSettings) -> String {
+        let precision = settings.precision.unwrap_or(default_precision);
+
+        let value = match &settings.rounding {
+            Some(rounding) => rounding.round(value, precision),
+            None => value,
+        };
+
+        format!("{:.*}", precision, value)
+    }
+
     fn print_temp(temp: Option<u64>, settings: &FormatSettings) -> String {
+        print_temp_exact(temp.map(|temp| temp as f64), None, settings)
+    }
+
+    /// Like print_temp, for sensors with a critical temperature, which the
+    /// temperature can be automatically colored relative to.
+    fn print_temp_crit(temp: Option<u64>, crit: Option<u64>, settings: &FormatSettings) -> String {
+        print_temp_exact(temp.map(|temp| temp as f64), crit, settings)
+    }
+
+    /// Like print_temp_crit, for averages and medians, which can fall in
+    /// between millidegrees.
+    fn print_temp_exact(temp: Option<f64>, crit: Option<u64>, settings: &FormatSettings) -> String {
         let temp = match temp {
             Some(temp) => temp,
-            None => return "N/A".into(),
+            None => return settings.pad("N/A".into()),
         };
 
-        let mut final_value: f64 = (temp as f64) / 1000.0;
+        let percent_of_crit = crit
+            .filter(|crit| *crit > 0)
+            .map(|crit| temp * 100.0 / crit as f64);
 
-        if let Some(target_unit) = &settings.target_unit {
-            final_value = settings.base_unit.convert_to(target_unit, final_value);
-        }
+        let final_value = final_temp(temp, settings);
 
+        // The glyph is that of the unit the value ends up in.
         let glyph = if settings.include_glyph {
-            settings.base_unit.to_str_glyph()
+            let final_unit = settings.target_unit.as_ref().unwrap_or(&settings.base_unit);
+            final_unit.to_str_glyph()
         } else {
             ""
         };
 
-        format!("{:.2}{}", final_value, glyph)
+        let text = settings.pad(format!(
+            "{}{}",
+            print_number(final_value, 2, settings),
+            glyph
+        ));
+
+        match settings.auto_color.band(final_value, percent_of_crit) {
+            Some(band) if settings.colors() => paint(text, band, settings),
+            _ => text,
+        }
+    }
+
+    /// Renders a temperature as a gauge that's empty at the floor, and full
+    /// at the critical temperature, both in the unit it's printed in.
+    fn print_gauge(temp: Option<f64>, crit: Option<u64>, settings: &FormatSettings) -> String {
+        let (temp, crit) = match (temp, crit.filter(|crit| *crit > 0)) {
+            (Some(temp), Some(crit)) => (temp, crit as f64),
+            _ => return settings.pad("N/A".into()),
+        };
+
+        let (final_value, final_crit) = (final_temp(temp, settings), final_temp(crit, settings));
+        let fraction = (final_value - settings.gauge_floor) / (final_crit - settings.gauge_floor);
+        let gauge = settings.pad(gauge::render(
+            fraction,
+            settings.gauge_width,
+            settings.gauge_style,
+        ));
+
+        match settings
+            .auto_color
+            .band(final_value, Some(temp * 100.0 / crit))
+        {
+            Some(band) if settings.colors() => paint(gauge, band, settings),
+            _ => gauge,
+        }
+    }
+
+    /// Converts a temperature from millidegrees to the unit it's printed in.
+    fn final_temp(millis: f64, settings: &FormatSettings) -> f64 {
+        units::final_temp(millis, &settings.base_unit, settings.target_unit.as_ref())
+    }
+
+    /// Renders temperatures as a sparkline, one character per temperature,
+    /// and a space for any that couldn't be read.
+    fn print_sparkline(temps: &[(Option<u64>, Option<u64>)], settings: &FormatSettings) -> String {
+        let finals: Vec<f64> = temps
+            .iter()
+            .filter_map(|(temp, _)| Some(final_temp((*temp)? as f64, settings)))
+            .collect();
+
+        let lowest = finals.iter().copied().reduce(f64::min).unwrap_or_default();
+        let highest = finals.iter().copied().reduce(f64::max).unwrap_or_default();
+
+        let mut sparkline = String::new();
+
+        for (temp, crit) in temps {
+            let temp = match temp {
+                Some(temp) => *temp as f64,
+                None => {
+                    sparkline.push(' ');
+                    continue;
+                }
+            };
+
+            let crit = crit.filter(|crit| *crit > 0).map(|crit| crit as f64);
+            let final_value = final_temp(temp, settings);
+
+            // With every temperature the same, there's no range to scale
+            // across, so they all sit at the bottom.
+            let fraction = match (&settings.spark_scale, crit) {
+                (SparkScale::Range, _) if highest > lowest => {
+                    (final_value - lowest) / (highest - lowest)
+                }
+                (SparkScale::Range, _) => 0.0,
+                (SparkScale::Critical, Some(crit)) => {
+                    let final_crit = final_temp(crit, settings);
+                    (final_value - settings.gauge_floor) / (final_crit - settings.gauge_floor)
+                }
+                (SparkScale::Critical, None) => {
+                    sparkline.push(' ');
+                    continue;
+                }
+            };
+
+            let spark = gauge::spark(fraction).to_string();
+            let percent_of_crit = crit.map(|crit| temp * 100.0 / crit);
+
+            sparkline += &match settings.auto_color.band(final_value, percent_of_crit) {
+                Some(band) if settings.colors() => paint(spark, band, settings),
+                _ => spark,
+            };
+        }
+
+        sparkline
+    }
+
+    /// Colors text with the color of its band, and then switches back to
+    /// whatever style was set before it.
+    fn paint(text: String, band: Band, settings: &FormatSettings) -> String {
+        let painted = Style {
+            fg: band.color(&settings.band_colors).clone(),
+            ..settings.style.clone()
+        };
+
+        format!(
+            "{}{}{}",
+            settings.dialect.transition(&settings.style, &painted),
+            text,
+            settings.dialect.transition(&painted, &settings.style)
+        )
+    }
+
+    fn print_freq(khz: Option<f64>, settings: &FormatSettings) -> String {
+        let khz = match khz {
+            Some(khz) => khz,
+            None => return settings.pad("N/A".into()),
+        };
+
+        let final_value = settings.freq_unit.convert_khz(khz);
+
+        let suffix = if settings.include_glyph {
+            settings.freq_unit.to_str_short()
+        } else {
+            ""
+        };
+
+        let default_precision = settings.freq_unit.default_precision();
+
+        settings.pad(format!(
+            "{}{}",
+            print_number(final_value, default_precision, settings),
+            suffix
+        ))
+    }
+
+    fn print_percent(percent: Option<f64>, settings: &FormatSettings) -> String {
+        let percent = match percent {
+            Some(percent) => percent,
+            None => return settings.pad("N/A".into()),
+        };
+
+        let suffix = if settings.include_glyph { "%" } else { "" };
+
+        settings.pad(format!("{}{}", print_number(percent, 1, settings), suffix))
+    }
+
+    fn print_count(count: Option<u64>, settings: &FormatSettings) -> String {
+        settings.pad(match count {
+            Some(count) => format!("{}", count),
+            None => "N/A".into(),
+        })
     }
 
     let mut output = String::new();
+    let mut saved_settings: Vec<FormatSettings> = Vec::new();
+
+    // Whether any style was set, and the output has to end with a reset.
+    let mut styled = false;
 
     for (key, values) in argm {
+        // Aggregate segments optionally take a socket, and otherwise cover
+        // the whole machine.
+        let socket = values.first().and_then(|s| s.parse::<u64>().ok());
+
         // Arguments that require no values.
         match key.as_str() {
-            "--glyph" | "-g" => output += fmts.base_unit.to_str_glyph(),
+            template::PUSH_SETTINGS => saved_settings.push(fmts.clone()),
+            template::POP_SETTINGS => {
+                if let Some(settings) = saved_settings.pop() {
+                    if fmts.colors() {
+                        output += &fmts.dialect.transition(&fmts.style, &settings.style);
+                    }
+
+                    fmts = settings;
+                }
+            }
+            // The glyph of the unit values end up in, like the one --use-glyph
+            // prints after them.
+            "--glyph" | "-g" => {
+                let final_unit = fmts.target_unit.as_ref().unwrap_or(&fmts.base_unit);
+                output += &fmts.dialect.escape(final_unit.to_str_glyph())
+            }
             "--avg" | "-av" => {
-                let average = ct.get_average()?;
-                output += &print_temp(Some(average), &fmts)
+                let ct = sensors.core_temp()?;
+                let average = ct.get_average(socket)?;
+                let crit = ct.get_socket_critical(socket).ok();
+                output += &print_temp_exact(Some(average), crit, &fmts)
             }
             "--median" | "-md" => {
-                let median = ct.get_median()?;
-                output += &print_temp(Some(median), &fmts)
+                let ct = sensors.core_temp()?;
+                let median = ct.get_median(socket)?;
+                let crit = ct.get_socket_critical(socket).ok();
+                output += &print_temp_exact(Some(median), crit, &fmts)
             }
             "--newline" | "-nl" | "-\\n" | "-cr" => {
                 output += "\n";
             }
-            "--strings" | "-s" => output += &values.join(" ").to_string(),
+            "--strings" | "-s" => output += &fmts.dialect.escape(&values.join(" ")),
             "--temp-min" | "-tm" => {
-                let min = ct.get_min()?;
-                output += &print_temp(Some(min), &fmts)
+                let ct = sensors.core_temp()?;
+                let min = ct.get_min(socket)?;
+                let crit = ct.get_socket_critical(socket).ok();
+                output += &print_temp_crit(Some(min), crit, &fmts)
             }
             "--temp-max" | "-tx" => {
-                let max = ct.get_max()?;
-                output += &print_temp(Some(max), &fmts)
+                let ct = sensors.core_temp()?;
+                let max = ct.get_max(socket)?;
+                let crit = ct.get_socket_critical(socket).ok();
+                output += &print_temp_crit(Some(max), crit, &fmts)
             }
             "--temp-package" | "-tp" => {
-                let package = ct.get_package()?;
-                output += &print_temp(Some(package), &fmts)
+                let ct = sensors.core_temp()?;
+                let socket = socket.unwrap_or(ct.get_default_socket());
+                let package = ct.get_package(socket)?;
+                let crit = ct.get_package_critical(socket).ok();
+                output += &print_temp_crit(Some(package), crit, &fmts)
+            }
+            "--reset" | "-rs" => {
+                if fmts.colors() {
+                    output += &fmts.dialect.reset(&fmts.style);
+                }
+
+                fmts.style = Style::default();
+            }
+            "--trend" | "-tr" | "--rate" | "-rt" => {
+                let source = values.first().map_or("avg", String::as_str);
+                let (sensor, temp, _) = read_source(sensors, source, values.get(1))?;
+
+                // In degrees of the unit temperatures are printed in.
+                let rate = temp.and_then(|temp| {
+                    let (previous, elapsed) = previous_reading(sensors, &sensor, temp)?;
+                    Some((final_temp(temp, &fmts) - final_temp(previous, &fmts)) / elapsed)
+                });
+
+                let text = match (key.as_str(), rate) {
+                    (_, None) => "N/A".to_string(),
+                    ("--trend" | "-tr", Some(rate)) if rate >= fmts.trend_threshold => {
+                        fmts.trend_glyphs[0].clone()
+                    }
+                    ("--trend" | "-tr", Some(rate)) if rate <= -fmts.trend_threshold => {
+                        fmts.trend_glyphs[1].clone()
+                    }
+                    ("--trend" | "-tr", Some(_)) => fmts.trend_glyphs[2].clone(),
+                    (_, Some(rate)) => {
+                        let glyph = if fmts.include_glyph {
+                            let final_unit = fmts.target_unit.as_ref().unwrap_or(&fmts.base_unit);
+                            format!("{}/s", final_unit.to_str_glyph())
+                        } else {
+                            String::new()
+                        };
+
+                        format!("{}{}", print_number(rate, 2, &fmts), glyph)
+                    }
+                };
+
+                // Escaped along with the padding, since glyphs are user text.
+                output += &fmts.pad(text);
+            }
+            "--reset-peaks" | "-rp" => sensors.reset_peaks(),
+            "--peak-max" | "-px" | "--peak-min" | "-pn" => {
+                let ct = sensors.core_temp()?;
+                let state = sensors.state();
+                peaks::update(ct, state);
+
+                let highest = matches!(key.as_str(), "--peak-max" | "-px");
+
+                let sensors_peaks = match values.first().map(String::as_str) {
+                    None | Some("package") => {
+                        let socket = values.get(1).and_then(|s| s.parse::<u64>().ok());
+                        let socket = socket.unwrap_or(ct.get_default_socket());
+
+                        vec![(
+                            Some(peaks::package_key(socket)),
+                            ct.get_package_critical(socket).ok(),
+                        )]
+                    }
+                    Some(_) => select_cores(sensors, values)?
+                        .into_iter()
+                        .map(|core| {
+                            (
+                                core.map(peaks::core_key),
+                                core.and_then(|core| ct.get_critical(core).ok()),
+                            )
+                        })
+                        .collect(),
+                };
+
+                let temps: Vec<String> = sensors_peaks
+                    .iter()
+                    .map(|(sensor, crit)| {
+                        let peak = sensor
+                            .as_deref()
+                            .and_then(|sensor| peaks::get(state, sensor));
+                        let peak = peak.map(
+                            |(highest_temp, lowest_temp)| {
+                                if highest {
+                                    highest_temp
+                                } else {
+                                    lowest_temp
+                                }
+                            },
+                        );
+
+                        print_temp_exact(peak, *crit, &fmts)
+                    })
+                    .collect();
+
+                output += &temps.join(", ");
             }
             "--core-count" | "-cc" => {
-                let core_count = ct.get_count();
-                output += &format!("{}", core_count).to_string()
+                let core_count = sensors.core_temp()?.get_count(socket)?;
+                output += &print_count(Some(core_count as u64), &fmts)
+            }
+            "--fan-fastest" | "-ff" => {
+                let fastest = sensors
+                    .hwmon()?
+                    .get_fan_fastest(values.first().map(String::as_str))?;
+                output += &print_count(Some(fastest), &fmts)
+            }
+            "--fan-slowest" | "-fs" => {
+                let slowest = sensors
+                    .hwmon()?
+                    .get_fan_slowest(values.first().map(String::as_str))?;
+                output += &print_count(Some(slowest), &fmts)
+            }
+            "--freq-avg" | "-qa" => {
+                let average = sensors.cpufreq()?.get_average()?;
+                output += &print_freq(Some(average), &fmts)
+            }
+            "--freq-median" | "-qd" => {
+                let median = sensors.cpufreq()?.get_median()?;
+                output += &print_freq(Some(median), &fmts)
+            }
+            "--freq-min" | "-qm" => {
+                let min = sensors.cpufreq()?.get_min()?;
+                output += &print_freq(Some(min as f64), &fmts)
+            }
+            "--freq-max" | "-qx" => {
+                let max = sensors.cpufreq()?.get_max()?;
+                output += &print_freq(Some(max as f64), &fmts)
+            }
+            "--usage" | "-u" | "--usage-user" | "-uu" | "--usage-system" | "-us"
+            | "--usage-iowait" | "-uw" | "--usage-steal" | "-ut" => {
+                let kind = match key.as_str() {
+                    "--usage-user" | "-uu" => UsageKind::User,
+                    "--usage-system" | "-us" => UsageKind::System,
+                    "--usage-iowait" | "-uw" => UsageKind::Iowait,
+                    "--usage-steal" | "-ut" => UsageKind::Steal,
+                    _ => UsageKind::Total,
+                };
+
+                let usage = sensors.cpu_usage();
+
+                // Without CPUS, the usage of all CPUs combined.
+                let cpus: Vec<Option<u64>> = if values.is_empty() {
+                    vec![None]
+                } else {
+                    select_cpus(usage.get_cpus(fmts.usage_interval)?, values)
+                        .into_iter()
+                        .map(Some)
+                        .collect()
+                };
+
+                let percents: Vec<String> = cpus
+                    .iter()
+                    .map(|cpu| {
+                        let percent = usage.get_usage(*cpu, kind, fmts.usage_interval);
+                        print_percent(percent.ok(), &fmts)
+                    })
+                    .collect();
+
+                output += &percents.join(", ");
+            }
+            "--socket-count" | "-sc" => {
+                let socket_count = sensors.core_temp()?.get_socket_count();
+                output += &print_count(Some(socket_count as u64), &fmts)
             }
 
             _ => (),
@@ -145,12 +880,234 @@ fn process_segments(ct: &CoreTemp, argm: Vec<(String, Vec<String>)>) -> ah::Resu
                 }
             }
 
+            ("--target-unit" | "-tu", "none" | "off") => {
+                fmts.target_unit = None;
+            }
+
             ("--target-unit" | "-tu", first) => {
                 if let Some(unit) = Unit::from_str(first) {
                     fmts.target_unit = Some(unit);
                 }
             }
 
+            ("--freq-unit" | "-qu", first) => {
+                if let Some(unit) = FreqUnit::from_str(first) {
+                    fmts.freq_unit = unit;
+                }
+            }
+
+            ("--usage-interval" | "-ui", first) => {
+                if let Ok(ms) = first.parse::<u64>() {
+                    fmts.usage_interval = Duration::from_millis(ms);
+                }
+            }
+
+            ("--gauge" | "-ga", first) => {
+                let (_, temp, crit) = read_source(sensors, first, values.get(1))?;
+                output += &print_gauge(temp, crit, &fmts);
+            }
+
+            ("--moving-avg" | "-ma" | "--window-min" | "-wm" | "--window-max" | "-wx", first) => {
+                let window = first
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                    .ok_or(ah::anyhow!("Invalid window '{}'", first))?;
+
+                let source = values.get(1).map_or("avg", String::as_str);
+                let (sensor, temp, crit) = read_source(sensors, source, values.get(2))?;
+                let history = sensors.history();
+
+                let value = temp.and_then(|temp| {
+                    history.record(&sensor, temp);
+
+                    match key.as_str() {
+                        "--moving-avg" | "-ma" => history.get_average(&sensor, window),
+                        "--window-min" | "-wm" => history.get_min(&sensor, window),
+                        _ => history.get_max(&sensor, window),
+                    }
+                });
+
+                output += &print_temp_exact(value, crit, &fmts);
+            }
+
+            ("--ema" | "-em", first) => {
+                let alpha = match first.parse::<f64>() {
+                    Ok(alpha) if alpha > 0.0 && alpha <= 1.0 => alpha,
+                    _ => return Err(ah::anyhow!("Invalid EMA alpha '{}'", first)),
+                };
+
+                let source = values.get(1).map_or("avg", String::as_str);
+                let (sensor, temp, crit) = read_source(sensors, source, values.get(2))?;
+                let history = sensors.history();
+
+                let value = temp.and_then(|temp| {
+                    history.record(&sensor, temp);
+                    history.get_ema(&sensor, alpha)
+                });
+
+                output += &print_temp_exact(value, crit, &fmts);
+            }
+
+            ("--trend-glyphs" | "-tg", _) => {
+                for (i, glyph) in values.iter().take(3).enumerate() {
+                    fmts.trend_glyphs[i] = glyph.clone();
+                }
+            }
+
+            ("--trend-threshold" | "-th", first) => {
+                if let Ok(threshold) = first.parse::<f64>() {
+                    fmts.trend_threshold = threshold.abs();
+                }
+            }
+
+            ("--width" | "-w", first) => {
+                if let Ok(width) = first.parse::<usize>() {
+                    fmts.width = width;
+                }
+            }
+
+            ("--align" | "-al", first) => {
+                if let Some(align) = Align::from_str(first) {
+                    fmts.align = align;
+                }
+            }
+
+            ("--fill-char" | "-fc", first) => {
+                if let Some(fill) = first.chars().next() {
+                    fmts.fill = fill;
+                }
+            }
+
+            ("--rule" | "-ru", first) => {
+                let string = values.get(1).map_or("-", String::as_str);
+
+                match first.parse::<usize>() {
+                    Ok(width) => output += &fmts.dialect.escape(&layout::rule(string, width)),
+                    Err(_) => return Err(ah::anyhow!("Invalid rule width '{}'", first)),
+                }
+            }
+
+            ("--sparkline" | "-sp", _) => {
+                let ct = sensors.core_temp()?;
+
+                let temps: Vec<(Option<u64>, Option<u64>)> = select_cores(sensors, values)?
+                    .into_iter()
+                    .map(|core| {
+                        (
+                            core.and_then(|core| ct.get_temp(core).ok()),
+                            core.and_then(|core| ct.get_critical(core).ok()),
+                        )
+                    })
+                    .collect();
+
+                output += &print_sparkline(&temps, &fmts);
+            }
+
+            ("--spark-scale" | "-ss", first) => {
+                if let Some(scale) = SparkScale::from_str(first) {
+                    fmts.spark_scale = scale;
+                }
+            }
+
+            ("--gauge-width" | "-gw", first) => {
+                if let Ok(width) = first.parse::<usize>() {
+                    fmts.gauge_width = width;
+                }
+            }
+
+            ("--gauge-floor" | "-gf", first) => {
+                if let Ok(floor) = first.parse::<f64>() {
+                    fmts.gauge_floor = floor;
+                }
+            }
+
+            ("--gauge-style" | "-gs", first) => {
+                if let Some(style) = GaugeStyle::from_str(first) {
+                    fmts.gauge_style = style;
+                }
+            }
+
+            ("--precision" | "-p", "default" | "reset") => {
+                fmts.precision = None;
+            }
+
+            ("--precision" | "-p", first) => {
+                if let Ok(precision) = first.parse::<usize>() {
+                    fmts.precision = Some(precision);
+                }
+            }
+
+            ("--rounding" | "-r", "default" | "reset") => {
+                fmts.rounding = None;
+            }
+
+            ("--rounding" | "-r", first) => {
+                if let Some(rounding) = Rounding::from_str(first) {
+                    fmts.rounding = Some(rounding);
+                }
+            }
+
+            ("--color" | "-co", first) => {
+                if let Some(mode) = ColorMode::from_str(first) {
+                    fmts.color_mode = mode;
+                }
+            }
+
+            ("--markup" | "-mk", first) => match Dialect::from_str(first) {
+                Some(dialect) => fmts.dialect = dialect,
+                None => return Err(ah::anyhow!("Unknown markup dialect '{}'", first)),
+            },
+
+            ("--fg" | "-fg" | "--bg" | "-bg", first) => {
+                let color = match Color::from_str(first) {
+                    Some(color) => color,
+                    None => return Err(ah::anyhow!("Invalid color '{}'", first)),
+                };
+
+                let mut style = fmts.style.clone();
+
+                if matches!(key.as_str(), "--bg" | "-bg") {
+                    style.bg = color;
+                } else {
+                    style.fg = color;
+                }
+
+                if fmts.colors() {
+                    output += &fmts.dialect.transition(&fmts.style, &style);
+                    styled = true;
+                }
+
+                fmts.style = style;
+            }
+
+            ("--bold" | "-b" | "--underline" | "-ul", first) => {
+                let on = match first {
+                    "true" | "yes" | "y" | "on" => true,
+                    "false" | "no" | "n" | "off" => false,
+                    _ => continue,
+                };
+
+                let mut style = fmts.style.clone();
+
+                if matches!(key.as_str(), "--bold" | "-b") {
+                    style.bold = on;
+                } else {
+                    style.underline = on;
+                }
+
+                if fmts.colors() {
+                    output += &fmts.dialect.transition(&fmts.style, &style);
+                    styled = true;
+                }
+
+                fmts.style = style;
+            }
+
+            ("--auto-color" | "-ac", _) => fmts.auto_color = parse_auto_color(values)?,
+
+            ("--band-colors" | "-bc", _) => parse_band_colors(values, &mut fmts.band_colors)?,
+
             ("--use-glyph" | "-ug", "true" | "yes" | "y" | "on") => {
                 fmts.include_glyph = true;
             }
@@ -159,18 +1116,14 @@ fn process_segments(ct: &CoreTemp, argm: Vec<(String, Vec<String>)>) -> ah::Resu
                 fmts.include_glyph = false;
             }
 
-            ("--temp" | "-t", first) => {
-                let cores: Vec<u64> = if matches!(first, "all" | "*") {
-                    (0..ct.get_count() as u64).collect()
-                } else {
-                    values
-                        .iter()
-                        .filter_map(|core| core.parse::<u64>().ok())
-                        .collect()
-                };
+            ("--temp" | "-t", _) => {
+                let ct = sensors.core_temp()?;
+                let cores = select_cores(sensors, values)?;
 
                 for (i, core) in cores.iter().enumerate() {
-                    let temp = print_temp(ct.get_temp(*core).ok(), &fmts);
+                    let crit = core.and_then(|core| ct.get_critical(core).ok());
+                    let temp =
+                        print_temp_crit(core.and_then(|core| ct.get_temp(core).ok()), crit, &fmts);
 
                     if i != cores.len() - 1 {
                         output += &format!("{}, ", temp);
@@ -180,13 +1133,11 @@ fn process_segments(ct: &CoreTemp, argm: Vec<(String, Vec<String>)>) -> ah::Resu
                 }
             }
             ("--core-critical" | "-cC", _) => {
-                let cores: Vec<u64> = values
-                    .iter()
-                    .filter_map(|core| core.parse::<u64>().ok())
-                    .collect();
+                let ct = sensors.core_temp()?;
+                let cores = select_cores(sensors, values)?;
 
                 for (i, core) in cores.iter().enumerate() {
-                    let crit = print_temp(ct.get_critical(*core).ok(), &fmts);
+                    let crit = print_temp(core.and_then(|core| ct.get_critical(core).ok()), &fmts);
 
                     if i != cores.len() - 1 {
                         output += &format!("{}, ", crit);
@@ -196,27 +1147,211 @@ fn process_segments(ct: &CoreTemp, argm: Vec<(String, Vec<String>)>) -> ah::Resu
                 }
             }
 
-            ("--core-alarm" | "-ca", _) => {
-                let cores: Vec<u64> = values
+            ("--zone-temp" | "-zt", _) => {
+                let zones = sensors.thermal_zones()?;
+
+                let temps: Vec<String> = values
+                    .iter()
+                    .map(|zone| {
+                        let temp = zones.get_temp(zone).ok().map(|temp| temp as f64);
+                        let crit = zones.get_trip(zone, "critical").ok();
+                        let crit = crit.and_then(|crit| u64::try_from(crit).ok());
+                        print_temp_exact(temp, crit, &fmts)
+                    })
+                    .collect();
+
+                output += &temps.join(", ");
+            }
+
+            ("--zone-passive" | "-zp" | "--zone-hot" | "-zh" | "--zone-critical" | "-zc", _) => {
+                let zones = sensors.thermal_zones()?;
+
+                let trip_type = match key.as_str() {
+                    "--zone-passive" | "-zp" => "passive",
+                    "--zone-hot" | "-zh" => "hot",
+                    _ => "critical",
+                };
+
+                let trips: Vec<String> = values
+                    .iter()
+                    .map(|zone| {
+                        let trip = zones.get_trip(zone, trip_type).ok();
+                        print_temp_exact(trip.map(|trip| trip as f64), None, &fmts)
+                    })
+                    .collect();
+
+                output += &trips.join(", ");
+            }
+
+            ("--hwmon" | "-hw", chip) => {
+                let hwmon = sensors.hwmon()?;
+                let labels = select_channels(hwmon, chip, "temp", &values[1..])?;
+
+                let temps: Vec<String> = labels
+                    .iter()
+                    .map(|label| {
+                        let temp = hwmon.get_temp(chip, label.as_deref(), "input");
+                        let crit = hwmon.get_temp(chip, label.as_deref(), "crit");
+                        print_temp_crit(temp.ok(), crit.ok(), &fmts)
+                    })
+                    .collect();
+
+                output += &temps.join(", ");
+            }
+
+            (
+                "--fan" | "-f" | "--fan-min" | "-fm" | "--fan-max" | "-fx" | "--fan-target" | "-ft",
+                chip,
+            ) => {
+                let hwmon = sensors.hwmon()?;
+                let labels = select_channels(hwmon, chip, "fan", &values[1..])?;
+
+                let attr = match key.as_str() {
+                    "--fan-min" | "-fm" => "min",
+                    "--fan-max" | "-fx" => "max",
+                    "--fan-target" | "-ft" => "target",
+                    _ => "input",
+                };
+
+                let speeds: Vec<String> = labels
+                    .iter()
+                    .map(|label| {
+                        print_count(hwmon.get_fan(chip, label.as_deref(), attr).ok(), &fmts)
+                    })
+                    .collect();
+
+                output += &speeds.join(", ");
+            }
+
+            ("--fan-pwm" | "-fp", chip) => {
+                let hwmon = sensors.hwmon()?;
+                let labels = select_channels(hwmon, chip, "pwm", &values[1..])?;
+
+                let duties: Vec<String> = labels
                     .iter()
-                    .filter_map(|core| core.parse::<u64>().ok())
+                    .map(|label| print_count(hwmon.get_pwm(chip, label.as_deref()).ok(), &fmts))
                     .collect();
 
+                output += &duties.join(", ");
+            }
+
+            (
+                "--drive" | "-dt" | "--drive-min" | "-dm" | "--drive-max" | "-dx"
+                | "--drive-critical" | "-dC",
+                drive,
+            ) => {
+                let hwmon = sensors.hwmon()?;
+
+                let attr = match key.as_str() {
+                    "--drive-min" | "-dm" => "min",
+                    "--drive-max" | "-dx" => "max",
+                    "--drive-critical" | "-dC" => "crit",
+                    _ => "input",
+                };
+
+                let labels: Vec<Option<&str>> = if values.len() > 1 {
+                    values[1..]
+                        .iter()
+                        .map(|label| Some(label.as_str()))
+                        .collect()
+                } else {
+                    vec![None]
+                };
+
+                let temps: Vec<String> = labels
+                    .iter()
+                    .map(|label| {
+                        let temp = hwmon.get_drive_temp(drive, *label, attr);
+
+                        // Only the drive's temperature is colored, not its limits.
+                        let crit = match attr {
+                            "input" => hwmon.get_drive_temp(drive, *label, "crit").ok(),
+                            _ => None,
+                        };
+
+                        print_temp_crit(temp.ok(), crit, &fmts)
+                    })
+                    .collect();
+
+                output += &temps.join(", ");
+            }
+
+            ("--freq" | "-q" | "--freq-hw-min" | "-ql" | "--freq-hw-max" | "-qh", _) => {
+                let cpufreq = sensors.cpufreq()?;
+                let cpus = select_cpus(cpufreq.get_cpus(), values);
+
+                let frequencies: Vec<String> = cpus
+                    .iter()
+                    .map(|cpu| {
+                        let khz = match key.as_str() {
+                            "--freq-hw-min" | "-ql" => cpufreq.get_hw_min(*cpu),
+                            "--freq-hw-max" | "-qh" => cpufreq.get_hw_max(*cpu),
+                            _ => cpufreq.get_freq(*cpu),
+                        };
+
+                        print_freq(khz.ok().map(|khz| khz as f64), &fmts)
+                    })
+                    .collect();
+
+                output += &frequencies.join(", ");
+            }
+
+            ("--core-cpus" | "-cs", _) => {
+                let ct = sensors.core_temp()?;
+                let topology = sensors.topology().ok();
+
+                let siblings: Vec<String> = select_cores(sensors, values)?
+                    .into_iter()
+                    .map(|core| {
+                        let (core, topology) = match (core, topology) {
+                            (Some(core), Some(topology)) if ct.has_physical_cores(core.socket) => {
+                                (core, topology)
+                            }
+                            _ => return "N/A".to_string(),
+                        };
+
+                        let cpus: Vec<String> = topology
+                            .get_siblings(core)
+                            .iter()
+                            .map(|cpu| cpu.to_string())
+                            .collect();
+
+                        match cpus.is_empty() {
+                            true => "N/A".to_string(),
+                            false => cpus.join(" "),
+                        }
+                    })
+                    .collect();
+
+                output += &siblings.join(", ");
+            }
+
+            ("--core-alarm" | "-ca", _) => {
+                let ct = sensors.core_temp()?;
+                let cores = select_cores(sensors, values)?;
+
                 for (i, core) in cores.iter().enumerate() {
-                    let alarm = match ct.get_critical_alarm(*core) {
-                        Ok(alarm) => match alarm {
+                    let alarm = match core.map(|core| ct.get_critical_alarm(core)) {
+                        Some(Ok(alarm)) => match alarm {
                             0 => "false".to_string(),
                             1 => "true".to_string(),
                             _ => "N/A/".to_string(),
                         },
 
-                        Err(_) => "N/A".to_string(),
+                        _ => "N/A".to_string(),
+                    };
+
+                    // Labeled by position, or the way cores are given,
+                    // SOCKET:CORE, once there's more than one socket.
+                    let label = match (ct.get_socket_count(), core) {
+                        (1, _) | (_, None) => (i + 1).to_string(),
+                        (_, Some(core)) => core.to_string(),
                     };
 
                     if i != cores.len() - 1 {
-                        output += &format!("Core {}: {}, ", i + 1, alarm);
+                        output += &format!("Core {}: {}, ", label, alarm);
                     } else {
-                        output += &format!("Core {}: {}", i + 1, alarm);
+                        output += &format!("Core {}: {}", label, alarm);
                     }
                 }
             }
@@ -225,9 +1360,351 @@ fn process_segments(ct: &CoreTemp, argm: Vec<(String, Vec<String>)>) -> ah::Resu
         }
     }
 
+    if styled && fmts.colors() {
+        output += &fmts.dialect.reset(&fmts.style);
+    }
+
     Ok(output)
 }
 
+/// Parses the values of --auto-color: off, crit [WARM HOT], or WARM HOT.
+fn parse_auto_color(values: &[String]) -> ah::Result<AutoColor> {
+    let number = |i: usize| values.get(i).and_then(|v| v.parse::<f64>().ok());
+
+    match values.first().map(String::as_str) {
+        Some("off" | "none") => Ok(AutoColor::Off),
+        Some("crit" | "critical") => Ok(AutoColor::Critical {
+            warm: number(1).unwrap_or(80.0),
+            hot: number(2).unwrap_or(95.0),
+        }),
+        _ => match (number(0), number(1)) {
+            (Some(warm), Some(hot)) => Ok(AutoColor::Absolute { warm, hot }),
+            _ => Err(ah::anyhow!("--auto-color needs WARM and HOT thresholds")),
+        },
+    }
+}
+
+/// The bands a bar's block or module is colored by, from the last
+/// --auto-color, --band-colors and units of its segments. Without
+/// --auto-color, warm from 80% and hot from 95% of the critical temperature.
+fn bar_bands(segments: &Segments) -> ah::Result<Bands> {
+    let auto_color = match last_option(segments, ["--auto-color", "-ac"]) {
+        Some(values) if !values.is_empty() => parse_auto_color(values)?,
+        _ => AutoColor::Critical {
+            warm: 80.0,
+            hot: 95.0,
+        },
+    };
+
+    let unit = |keys| {
+        last_option(segments, keys)
+            .and_then(|values| values.first())
+            .and_then(|unit| Unit::from_str(unit))
+    };
+
+    let mut colors = BAND_COLORS;
+
+    if let Some(values) = last_option(segments, ["--band-colors", "-bc"]) {
+        parse_band_colors(values, &mut colors)?;
+    }
+
+    Ok(Bands {
+        auto_color,
+        colors,
+        base_unit: unit(["--base-unit", "-bu"]).unwrap_or(Unit::Celcius),
+        target_unit: unit(["--target-unit", "-tu"]),
+    })
+}
+
+/// Sets the colors of --band-colors, from the normal band up.
+fn parse_band_colors(values: &[String], colors: &mut [Color; 3]) -> ah::Result<()> {
+    for (i, value) in values.iter().take(3).enumerate() {
+        match Color::from_str(value) {
+            Some(color) => colors[i] = color,
+            None => return Err(ah::anyhow!("Invalid color '{}'", value)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the [INTERVAL [COUNT]] values of an output mode, the interval in
+/// milliseconds.
+fn parse_schedule(options: &[String], mode: &str) -> ah::Result<(Option<Duration>, Option<u64>)> {
+    let interval = match options.first() {
+        Some(interval) => {
+            Some(Duration::from_millis(interval.parse().map_err(|_| {
+                ah::anyhow!("Invalid {} interval '{}'", mode, interval)
+            })?))
+        }
+        None => None,
+    };
+
+    let count = match options.get(1) {
+        Some(count) => Some(
+            count
+                .parse::<u64>()
+                .map_err(|_| ah::anyhow!("Invalid {} count '{}'", mode, count))?,
+        ),
+        None => None,
+    };
+
+    Ok((interval, count))
+}
+
+/// Speaks the i3bar protocol on stdout, evaluating the segments into a block
+/// every INTERVAL milliseconds, forever or COUNT times.
+fn run_i3bar(
+    sensors: &Sensors,
+    arguments: &Segments,
+    segments: &Segments,
+    short_segments: Option<&Segments>,
+    options: &[String],
+) -> ah::Result<()> {
+    let (interval, count) = parse_schedule(options, "i3bar")?;
+    let interval = interval.unwrap_or(I3BAR_INTERVAL);
+
+    let block_name = last_option(arguments, ["--block-name", "-bn"]).unwrap_or_default();
+    let name = block_name.first().map_or("coretempf", String::as_str);
+    let instance = block_name.get(1);
+
+    let block_color = last_option(arguments, ["--block-color", "-bk"]).and_then(|v| v.first());
+
+    let block_color = match block_color.map(String::as_str) {
+        Some("auto") | None => None,
+        Some(color) => match Color::from_str(color) {
+            Some(color) => Some(color),
+            None => return Err(ah::anyhow!("Invalid color '{}'", color)),
+        },
+    };
+
+    let pango = last_option(arguments, ["--markup", "-mk"])
+        .and_then(|values| values.first())
+        .and_then(|dialect| Dialect::from_str(dialect))
+        == Some(Dialect::Pango);
+
+    let bands = bar_bands(segments)?;
+
+    let short_segments =
+        short_segments.map(|short| with_markup(last_option(segments, ["--markup", "-mk"]), short));
+
+    let evaluate = |segments| {
+        process_segments(sensors, segments).map_err(|e| ah::anyhow!("Segment parser error: {}", e))
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}\n[", bar::I3BAR_HEADER)?;
+
+    let mut tick = 0;
+
+    while count.is_none_or(|count| tick < count) {
+        if tick > 0 {
+            std::thread::sleep(interval);
+        }
+
+        sensors.tick();
+
+        let ct = sensors.core_temp().ok();
+
+        // Without a color of its own, the block is colored by the band of
+        // the hottest core.
+        let color = match &block_color {
+            Some(color) => color.to_hex(),
+            None => {
+                let band = ct.and_then(|ct| bands.hottest(ct));
+                band.and_then(|band| band.color(&bands.colors).to_hex())
+            }
+        };
+
+        let block = Block {
+            full_text: evaluate(segments)?,
+            short_text: short_segments.as_deref().map(evaluate).transpose()?,
+            color,
+            urgent: ct.is_some_and(bar::any_alarm),
+            name: name.to_string(),
+            instance: instance.cloned(),
+            pango,
+        };
+
+        let separator = if tick > 0 { "," } else { "" };
+        writeln!(stdout, "{}[{}]", separator, block.to_json())?;
+        stdout.flush()?;
+        sensors.save_state();
+
+        tick += 1;
+    }
+
+    Ok(())
+}
+
+/// Prints the JSON of a Waybar custom module with return-type json, once,
+/// or every INTERVAL milliseconds, forever or COUNT times.
+fn run_waybar(
+    sensors: &Sensors,
+    segments: &Segments,
+    tooltip_segments: Option<&Segments>,
+    options: &[String],
+) -> ah::Result<()> {
+    let (interval, count) = parse_schedule(options, "waybar")?;
+    let count = if interval.is_none() { Some(1) } else { count };
+
+    let bands = bar_bands(segments)?;
+
+    // Waybar parses both the text and the tooltip as Pango markup.
+    let pango = ["pango".to_string()];
+    let markup = last_option(segments, ["--markup", "-mk"]).unwrap_or(&pango);
+    let segments = with_markup(Some(&pango), segments);
+    let tooltip_segments = tooltip_segments.map(|tooltip| with_markup(Some(markup), tooltip));
+
+    let evaluate = |segments| {
+        process_segments(sensors, segments).map_err(|e| ah::anyhow!("Segment parser error: {}", e))
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    let mut tick = 0;
+
+    while count.is_none_or(|count| tick < count) {
+        if let Some(interval) = interval.filter(|_| tick > 0) {
+            std::thread::sleep(interval);
+        }
+
+        sensors.tick();
+
+        let ct = sensors.core_temp().ok();
+        let percentage = ct.and_then(bar::hottest_percent);
+
+        let module = WaybarModule {
+            text: evaluate(&segments)?,
+            tooltip: tooltip_segments.as_deref().map(evaluate).transpose()?,
+            band: ct.and_then(|ct| bands.hottest(ct)),
+            percentage,
+        };
+
+        writeln!(stdout, "{}", module.to_json())?;
+        stdout.flush()?;
+        sensors.save_state();
+
+        tick += 1;
+    }
+
+    Ok(())
+}
+
+/// Prints the segments every INTERVAL milliseconds, forever or COUNT times,
+/// for bars that read lines from a long-lived process. Sensors are only
+/// discovered once, rather than on every run.
+fn run_watch(sensors: &Sensors, segments: &Segments, options: &[String]) -> ah::Result<()> {
+    let (interval, count) = parse_schedule(options, "watch")?;
+    let interval = interval.ok_or(ah::anyhow!("--watch needs an INTERVAL"))?;
+
+    let mut stdout = std::io::stdout().lock();
+    let mut tick = 0;
+
+    while count.is_none_or(|count| tick < count) {
+        if tick > 0 {
+            std::thread::sleep(interval);
+        }
+
+        sensors.tick();
+
+        let output = process_segments(sensors, segments)
+            .map_err(|e| ah::anyhow!("Segment parser error: {}", e))?;
+
+        writeln!(stdout, "{}", output)?;
+        stdout.flush()?;
+        sensors.save_state();
+
+        tick += 1;
+    }
+
+    Ok(())
+}
+
+/// Redraws the segments in place every INTERVAL milliseconds, forever or
+/// COUNT times, as a small live dashboard on a terminal. Each frame moves
+/// the cursor back up over the previous one, or with alt_screen, draws on
+/// the alternate screen. The terminal is restored on Ctrl-C.
+fn run_live(
+    sensors: &Sensors,
+    segments: &Segments,
+    options: &[String],
+    alt_screen: bool,
+) -> ah::Result<()> {
+    let (interval, count) = parse_schedule(options, "live")?;
+    let interval = interval.ok_or(ah::anyhow!("--live needs an INTERVAL"))?;
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst))?;
+
+    let mut stdout = std::io::stdout().lock();
+
+    // Hide the cursor, so it doesn't flicker over the frame.
+    write!(
+        stdout,
+        "{}\x1b[?25l",
+        if alt_screen { "\x1b[?1049h" } else { "" }
+    )?;
+
+    let mut previous_lines = 0;
+    let mut tick = 0;
+
+    let result = (|| -> ah::Result<()> {
+        while running.load(Ordering::SeqCst) && count.is_none_or(|count| tick < count) {
+            if tick > 0 {
+                let start = Instant::now();
+
+                // Sleep in slices, so that Ctrl-C doesn't wait out a long
+                // interval.
+                while running.load(Ordering::SeqCst) && start.elapsed() < interval {
+                    std::thread::sleep(interval.saturating_sub(start.elapsed()).min(LIVE_POLL));
+                }
+
+                if !running.load(Ordering::SeqCst) {
+                    break;
+                }
+            }
+
+            sensors.tick();
+
+            let output = process_segments(sensors, segments)
+                .map_err(|e| ah::anyhow!("Segment parser error: {}", e))?;
+
+            let lines: Vec<&str> = output.split('\n').collect();
+
+            // Back to the top left of the previous frame.
+            if alt_screen {
+                write!(stdout, "\x1b[H")?;
+            } else if previous_lines > 1 {
+                write!(stdout, "\r\x1b[{}A", previous_lines - 1)?;
+            } else {
+                write!(stdout, "\r")?;
+            }
+
+            // Every line clears what's left of the previous frame's line,
+            // and whatever is below clears lines the frame no longer has.
+            write!(stdout, "{}\x1b[K\x1b[J", lines.join("\x1b[K\n"))?;
+            stdout.flush()?;
+            sensors.save_state();
+
+            previous_lines = lines.len();
+            tick += 1;
+        }
+
+        Ok(())
+    })();
+
+    // The last frame stays on the normal screen.
+    if alt_screen {
+        write!(stdout, "\x1b[?1049l")?;
+    }
+
+    writeln!(stdout, "\x1b[?25h")?;
+    stdout.flush()?;
+
+    result
+}
+
 fn main() {
     let arguments = parse_args();
 
@@ -242,15 +1719,79 @@ fn main() {
         exit_with_usage(0)
     }
 
-    let core_temp = match CoreTemp::try_new() {
-        Ok(core_temp) => core_temp,
+    let arguments = match expand_templates(arguments) {
+        Ok(arguments) => arguments,
         Err(e) => {
-            eprintln!("Hwmon error: {}", e);
+            eprintln!("Template error: {}", e);
             std::process::exit(1);
         }
     };
 
-    let output = match process_segments(&core_temp, arguments) {
+    let sysfs_root = fs_root(
+        &arguments,
+        ["--sysfs-root", "-sr"],
+        SYSFS_ROOT_ENV,
+        SYSFS_ROOT,
+    );
+    let procfs_root = fs_root(
+        &arguments,
+        ["--procfs-root", "-pr"],
+        PROCFS_ROOT_ENV,
+        PROCFS_ROOT,
+    );
+
+    let sensors = Sensors::new(sysfs_root, &procfs_root, state_path());
+
+    if let Some(options) = last_option(&arguments, ["--i3bar", "-i3"]) {
+        let (segments, short_segments) = split_sequence(&arguments, &SHORT_TEXT_KEYS);
+
+        if let Err(e) = run_i3bar(&sensors, &arguments, segments, short_segments, options) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    if let Some(options) = last_option(&arguments, ["--waybar", "-wb"]) {
+        let (segments, tooltip_segments) = split_sequence(&arguments, &TOOLTIP_KEYS);
+
+        if let Err(e) = run_waybar(&sensors, segments, tooltip_segments, options) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    // Second sequences only mean something to the output modes.
+    let (segments, _) = split_sequence(&arguments, &[SHORT_TEXT_KEYS, TOOLTIP_KEYS].concat());
+
+    if let Some(options) = last_option(&arguments, ["--live", "-lv"]) {
+        let alt_screen = last_option(&arguments, ["--alt-screen", "-as"])
+            .and_then(|values| values.first())
+            .is_some_and(|value| matches!(value.as_str(), "true" | "yes" | "y" | "on"));
+
+        if let Err(e) = run_live(&sensors, segments, options, alt_screen) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    if let Some(options) = last_option(&arguments, ["--watch", "-wa"]) {
+        if let Err(e) = run_watch(&sensors, segments, options) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    sensors.tick();
+
+    let output = match process_segments(&sensors, segments) {
         Ok(s) => s,
         Err(e) => {
             eprintln!("Segment parser error: {}", e);
@@ -259,4 +1800,5 @@ fn main() {
     };
 
     println!("{}", output);
+    sensors.save_state();
 }
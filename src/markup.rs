@@ -0,0 +1,202 @@
+use crate::color::{Color, COLOR_NAMES};
+
+/// The colors and emphasis in effect at some point of the output.
+#[derive(Clone, Default, PartialEq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+/// How styles are written into the output: ANSI escape sequences for
+/// terminals, or the markup of a status bar.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Dialect {
+    Ansi,
+    Pango,
+    Tmux,
+    Lemonbar,
+    Polybar,
+    Xmobar,
+}
+
+impl Dialect {
+    pub fn from_str(s: &str) -> Option<Dialect> {
+        match s {
+            "ansi" | "terminal" => Some(Dialect::Ansi),
+            "pango" | "i3" | "sway" | "waybar" => Some(Dialect::Pango),
+            "tmux" => Some(Dialect::Tmux),
+            "lemonbar" | "bar" => Some(Dialect::Lemonbar),
+            "polybar" => Some(Dialect::Polybar),
+            "xmobar" => Some(Dialect::Xmobar),
+            _ => None,
+        }
+    }
+
+    /// Escapes user text, such as that of -s, so that the bar displays it
+    /// as is rather than interpreting it as markup.
+    pub fn escape(&self, text: &str) -> String {
+        match self {
+            Dialect::Ansi => text.to_string(),
+            Dialect::Pango => text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('\'', "&#39;")
+                .replace('"', "&quot;"),
+            Dialect::Tmux => text.replace('#', "##"),
+            Dialect::Lemonbar | Dialect::Polybar => text.replace('%', "%%"),
+            Dialect::Xmobar => text.replace('<', "<raw=1:</>"),
+        }
+    }
+
+    /// The markup that switches the output from one style to another.
+    pub fn transition(&self, from: &Style, to: &Style) -> String {
+        if from == to {
+            return String::new();
+        }
+
+        match self {
+            Dialect::Ansi => {
+                let mut codes = String::new();
+
+                if from.fg != to.fg {
+                    codes += &to.fg.to_ansi(false);
+                }
+
+                if from.bg != to.bg {
+                    codes += &to.bg.to_ansi(true);
+                }
+
+                if from.bold != to.bold {
+                    codes += if to.bold { "\x1b[1m" } else { "\x1b[22m" };
+                }
+
+                if from.underline != to.underline {
+                    codes += if to.underline { "\x1b[4m" } else { "\x1b[24m" };
+                }
+
+                codes
+            }
+
+            // Pango and xmobar markup nests, so the open tag is closed, and
+            // one for the new style opened.
+            Dialect::Pango | Dialect::Xmobar => self.close(from) + &self.open(to),
+
+            Dialect::Tmux => {
+                let mut attributes = Vec::new();
+
+                if from.fg != to.fg {
+                    attributes.push(format!("fg={}", tmux_color(&to.fg)));
+                }
+
+                if from.bg != to.bg {
+                    attributes.push(format!("bg={}", tmux_color(&to.bg)));
+                }
+
+                if from.bold != to.bold {
+                    attributes.push(if to.bold { "bold" } else { "nobold" }.to_string());
+                }
+
+                if from.underline != to.underline {
+                    attributes.push(
+                        if to.underline {
+                            "underscore"
+                        } else {
+                            "nounderscore"
+                        }
+                        .to_string(),
+                    );
+                }
+
+                format!("#[{}]", attributes.join(","))
+            }
+
+            // Neither has bold text without configuring a second font.
+            Dialect::Lemonbar | Dialect::Polybar => {
+                let mut tags = String::new();
+
+                if from.fg != to.fg {
+                    tags += &format!("%{{F{}}}", to.fg.to_hex().unwrap_or("-".into()));
+                }
+
+                if from.bg != to.bg {
+                    tags += &format!("%{{B{}}}", to.bg.to_hex().unwrap_or("-".into()));
+                }
+
+                if from.underline != to.underline {
+                    tags += if to.underline { "%{+u}" } else { "%{-u}" };
+                }
+
+                tags
+            }
+        }
+    }
+
+    /// The markup that ends all styling, for --reset and the end of output.
+    pub fn reset(&self, from: &Style) -> String {
+        match self {
+            Dialect::Ansi => "\x1b[0m".to_string(),
+            Dialect::Tmux => "#[default]".to_string(),
+            _ => self.transition(from, &Style::default()),
+        }
+    }
+
+    fn open(&self, style: &Style) -> String {
+        if *style == Style::default() {
+            return String::new();
+        }
+
+        match self {
+            Dialect::Pango => {
+                let mut attributes = String::new();
+
+                if let Some(fg) = style.fg.to_hex() {
+                    attributes += &format!(" foreground=\"{}\"", fg);
+                }
+
+                if let Some(bg) = style.bg.to_hex() {
+                    attributes += &format!(" background=\"{}\"", bg);
+                }
+
+                if style.bold {
+                    attributes += " weight=\"bold\"";
+                }
+
+                if style.underline {
+                    attributes += " underline=\"single\"";
+                }
+
+                format!("<span{}>", attributes)
+            }
+
+            // xmobar only has colors, and a background needs a foreground.
+            Dialect::Xmobar => match (style.fg.to_hex(), style.bg.to_hex()) {
+                (Some(fg), Some(bg)) => format!("<fc={},{}>", fg, bg),
+                (Some(fg), None) => format!("<fc={}>", fg),
+                _ => String::new(),
+            },
+
+            _ => String::new(),
+        }
+    }
+
+    fn close(&self, style: &Style) -> String {
+        match self {
+            Dialect::Pango if *style != Style::default() => "</span>".to_string(),
+            Dialect::Xmobar if style.fg != Color::Default => "</fc>".to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+fn tmux_color(color: &Color) -> String {
+    match color {
+        Color::Default => "default".to_string(),
+        Color::Named(n) if *n < 8 => COLOR_NAMES[*n as usize].to_string(),
+        Color::Named(n) => format!("bright{}", COLOR_NAMES[(*n - 8) as usize]),
+        Color::Indexed(n) => format!("colour{}", n),
+        Color::Rgb(..) => color.to_hex().unwrap_or_default(),
+    }
+}
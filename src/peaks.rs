@@ -0,0 +1,65 @@
+use crate::state::State;
+use crate::temps::{CoreId, CoreTemp};
+
+const PREFIX: &str = "peak ";
+
+pub fn core_key(core: CoreId) -> String {
+    format!("{}core {}", PREFIX, core)
+}
+
+pub fn package_key(socket: u64) -> String {
+    format!("{}package {}", PREFIX, socket)
+}
+
+/// Folds the current readings of every core and package into the highest
+/// and lowest readings since boot, or since the last reset.
+pub fn update(ct: &CoreTemp, state: &State) {
+    for core in ct.get_cores(None).unwrap_or_default() {
+        if let Ok(temp) = ct.get_temp(core) {
+            fold(state, &core_key(core), temp as f64);
+        }
+    }
+
+    for socket in ct.get_sockets() {
+        if let Ok(temp) = ct.get_package(socket) {
+            fold(state, &package_key(socket), temp as f64);
+        }
+    }
+}
+
+fn fold(state: &State, key: &str, temp: f64) {
+    let peaks = match get(state, key) {
+        Some((highest, lowest)) => (highest.max(temp), lowest.min(temp)),
+        None => (temp, temp),
+    };
+
+    // Only set when they change, so the state isn't written on every run.
+    if get(state, key) != Some(peaks) {
+        state.set_merged(key, format!("{} {}", peaks.0, peaks.1), merge);
+    }
+}
+
+/// Keeps the highest and lowest of two runs' peaks, should another run have
+/// saved a peak between this run reading and saving them.
+fn merge(saved: &str, peaks: &str) -> String {
+    match (parse(saved), parse(peaks)) {
+        (Some(saved), Some(peaks)) => {
+            format!("{} {}", saved.0.max(peaks.0), saved.1.min(peaks.1))
+        }
+        _ => peaks.to_string(),
+    }
+}
+
+fn parse(peaks: &str) -> Option<(f64, f64)> {
+    let (highest, lowest) = peaks.split_once(' ')?;
+    Some((highest.parse().ok()?, lowest.parse().ok()?))
+}
+
+/// The highest and lowest reading of a sensor, in millidegrees.
+pub fn get(state: &State, key: &str) -> Option<(f64, f64)> {
+    parse(&state.get(key)?)
+}
+
+pub fn reset(state: &State) {
+    state.remove_prefix(PREFIX);
+}
@@ -0,0 +1,155 @@
+use anyhow as ah;
+use std::cell::{Cell, OnceCell};
+use std::path::{Path, PathBuf};
+
+use crate::cpufreq::CpuFreq;
+use crate::history::History;
+use crate::hwmon::Hwmon;
+use crate::peaks;
+use crate::state::State;
+use crate::temps::CoreTemp;
+use crate::thermal::ThermalZones;
+use crate::topology::Topology;
+use crate::usage::CpuUsage;
+
+const PROCFS_BOOT_ID: &str = "sys/kernel/random/boot_id";
+
+/// The sensor backends, each discovered the first time a segment needs it.
+/// This way a sequence that only reads thermal zones works on machines
+/// without a CPU hwmon driver, and backends the sequence doesn't use are
+/// never scanned.
+pub struct Sensors {
+    sysfs_root: PathBuf,
+    cpu_usage: CpuUsage,
+    history: History,
+    state: State,
+    peaks_reset: Cell<bool>,
+    core_temp: OnceCell<Result<CoreTemp, String>>,
+    hwmon: OnceCell<Result<Hwmon, String>>,
+    thermal_zones: OnceCell<Result<ThermalZones, String>>,
+    cpufreq: OnceCell<Result<CpuFreq, String>>,
+    topology: OnceCell<Result<Topology, String>>,
+}
+
+impl Sensors {
+    pub fn new(sysfs_root: PathBuf, procfs_root: &Path, state_path: Option<PathBuf>) -> Sensors {
+        Sensors {
+            sysfs_root,
+            cpu_usage: CpuUsage::new(procfs_root),
+            history: History::new(),
+            state: State::new(state_path, read_boot_id(procfs_root)),
+            peaks_reset: Cell::new(false),
+            core_temp: OnceCell::new(),
+            hwmon: OnceCell::new(),
+            thermal_zones: OnceCell::new(),
+            cpufreq: OnceCell::new(),
+            topology: OnceCell::new(),
+        }
+    }
+
+    /// Marks the start of an evaluation of the segment sequence. Readings
+    /// that are measured over time, like CPU usage, are taken once per tick.
+    pub fn tick(&self) {
+        self.cpu_usage.tick();
+        self.history.tick();
+        self.state.tick();
+
+        // Peaks are updated on every run, whatever the segments read, so
+        // that they catch how hot it got while nothing was looking. Only
+        // when they're kept across runs, so that otherwise, sequences that
+        // don't read coretemp still don't scan for it.
+        if self.state.is_persistent() {
+            if let Ok(ct) = self.core_temp() {
+                peaks::update(ct, &self.state);
+            }
+        }
+    }
+
+    pub fn cpu_usage(&self) -> &CpuUsage {
+        &self.cpu_usage
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Starts the peaks over from the current readings. Only once per run,
+    /// so that in a repeating mode, they still build up after the reset.
+    pub fn reset_peaks(&self) {
+        if self.peaks_reset.replace(true) {
+            return;
+        }
+
+        peaks::reset(&self.state);
+
+        if let Ok(ct) = self.core_temp() {
+            peaks::update(ct, &self.state);
+        }
+    }
+
+    /// Saves the state after an evaluation of the segment sequence. Failing
+    /// to is only worth a warning, since the output is still right.
+    pub fn save_state(&self) {
+        if let Err(e) = self.state.save() {
+            eprintln!("State error: {:#}", e);
+        }
+    }
+
+    pub fn core_temp(&self) -> ah::Result<&CoreTemp> {
+        self.core_temp
+            .get_or_init(|| {
+                CoreTemp::try_new(&self.sysfs_root).map_err(|e| format!("Hwmon error: {:#}", e))
+            })
+            .as_ref()
+            .map_err(|e| ah::anyhow!("{}", e))
+    }
+
+    pub fn hwmon(&self) -> ah::Result<&Hwmon> {
+        self.hwmon
+            .get_or_init(|| {
+                Hwmon::try_new(&self.sysfs_root).map_err(|e| format!("Hwmon error: {:#}", e))
+            })
+            .as_ref()
+            .map_err(|e| ah::anyhow!("{}", e))
+    }
+
+    pub fn thermal_zones(&self) -> ah::Result<&ThermalZones> {
+        self.thermal_zones
+            .get_or_init(|| {
+                ThermalZones::try_new(&self.sysfs_root)
+                    .map_err(|e| format!("Thermal zone error: {:#}", e))
+            })
+            .as_ref()
+            .map_err(|e| ah::anyhow!("{}", e))
+    }
+
+    pub fn cpufreq(&self) -> ah::Result<&CpuFreq> {
+        self.cpufreq
+            .get_or_init(|| {
+                CpuFreq::try_new(&self.sysfs_root).map_err(|e| format!("Cpufreq error: {:#}", e))
+            })
+            .as_ref()
+            .map_err(|e| ah::anyhow!("{}", e))
+    }
+
+    pub fn topology(&self) -> ah::Result<&Topology> {
+        self.topology
+            .get_or_init(|| {
+                Topology::try_new(&self.sysfs_root).map_err(|e| format!("Topology error: {:#}", e))
+            })
+            .as_ref()
+            .map_err(|e| ah::anyhow!("{}", e))
+    }
+}
+
+/// Identifies the current boot, so that state kept during an earlier boot
+/// is forgotten.
+fn read_boot_id(procfs_root: &Path) -> Option<String> {
+    std::fs::read_to_string(procfs_root.join(PROCFS_BOOT_ID))
+        .ok()
+        .map(|boot_id| boot_id.trim().to_string())
+}
@@ -0,0 +1,210 @@
+use ah::Context;
+use anyhow as ah;
+use std::cell::{OnceCell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The key of the boot the values were kept during.
+const BOOT_ID: &str = "boot_id";
+
+/// Values that outlive a run, like the previous reading of a sensor for
+/// --trend, kept in a file of key and value lines separated by a tab. Most
+/// bars run coretempf once per update, so this is the only way it sees
+/// readings of earlier updates.
+pub struct State {
+    path: Option<PathBuf>,
+    boot_id: Option<String>,
+    entries: OnceCell<RefCell<HashMap<String, String>>>,
+    // Entries set and key prefixes removed since the last save, how to merge
+    // entries with those saved by other runs, and values before this tick.
+    changed: RefCell<HashMap<String, String>>,
+    removed: RefCell<HashSet<String>>,
+    merges: RefCell<HashMap<String, Merge>>,
+    before_tick: RefCell<HashMap<String, Option<String>>>,
+}
+
+/// Merges a value with the one another run saved in the meantime, given as
+/// merge(saved, value).
+pub type Merge = fn(&str, &str) -> String;
+
+impl State {
+    /// Without a path, values only last as long as the process does. Values
+    /// kept during another boot than boot_id are forgotten.
+    pub fn new(path: Option<PathBuf>, boot_id: Option<String>) -> State {
+        State {
+            path,
+            boot_id,
+            entries: OnceCell::new(),
+            changed: RefCell::new(HashMap::new()),
+            removed: RefCell::new(HashSet::new()),
+            merges: RefCell::new(HashMap::new()),
+            before_tick: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_persistent(&self) -> bool {
+        self.path.is_some()
+    }
+
+    fn entries(&self) -> &RefCell<HashMap<String, String>> {
+        self.entries.get_or_init(|| RefCell::new(self.load()))
+    }
+
+    /// Reads the file as other runs left it, with the values that are yet to
+    /// be saved on top.
+    fn load(&self) -> HashMap<String, String> {
+        let mut entries = self.path.as_deref().map(read).unwrap_or_default();
+
+        if self.forget_other_boot(&mut entries) {
+            if let Some(boot_id) = &self.boot_id {
+                self.changed
+                    .borrow_mut()
+                    .insert(BOOT_ID.to_string(), boot_id.clone());
+            }
+        }
+
+        let removed = self.removed.borrow();
+        entries.retain(|key, _| !removed.iter().any(|prefix| key.starts_with(prefix)));
+        entries.extend(self.changed.borrow().clone());
+        entries
+    }
+
+    /// Clears values kept during another boot, and returns whether it did.
+    fn forget_other_boot(&self, entries: &mut HashMap<String, String>) -> bool {
+        match &self.boot_id {
+            Some(boot_id) if entries.get(BOOT_ID) != Some(boot_id) => {
+                entries.clear();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Re-reads the file, since a long-lived run would otherwise keep going
+    /// from what it read at its start, and overwrite what other runs saved
+    /// since, like higher peaks.
+    pub fn tick(&self) {
+        self.before_tick.borrow_mut().clear();
+
+        if let Some(entries) = self.entries.get().filter(|_| self.is_persistent()) {
+            *entries.borrow_mut() = self.load();
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.entries().borrow().get(key).cloned()
+    }
+
+    /// The value as it was before this tick, so that segments reading the
+    /// same sensor all compare against the same earlier reading.
+    pub fn get_previous(&self, key: &str) -> Option<String> {
+        match self.before_tick.borrow().get(key) {
+            Some(before) => before.clone(),
+            None => self.get(key),
+        }
+    }
+
+    pub fn set(&self, key: &str, value: String) {
+        let mut entries = self.entries().borrow_mut();
+
+        self.before_tick
+            .borrow_mut()
+            .entry(key.to_string())
+            .or_insert_with(|| entries.get(key).cloned());
+
+        entries.insert(key.to_string(), value.clone());
+        self.changed.borrow_mut().insert(key.to_string(), value);
+    }
+
+    /// Like set, but the value is merged with whatever another run saved in
+    /// the meantime, when it's saved.
+    pub fn set_merged(&self, key: &str, value: String, merge: Merge) {
+        self.set(key, value);
+        self.merges.borrow_mut().insert(key.to_string(), merge);
+    }
+
+    /// Removes every value whose key starts with prefix.
+    pub fn remove_prefix(&self, prefix: &str) {
+        self.entries()
+            .borrow_mut()
+            .retain(|key, _| !key.starts_with(prefix));
+        self.changed
+            .borrow_mut()
+            .retain(|key, _| !key.starts_with(prefix));
+        self.removed.borrow_mut().insert(prefix.to_string());
+    }
+
+    /// Writes the values set since the last save. They're merged into the
+    /// file as it is now, rather than as it was read, so that other runs,
+    /// e.g. other modules of the same bar, don't lose theirs.
+    pub fn save(&self) -> ah::Result<()> {
+        let unsaved = !self.changed.borrow().is_empty() || !self.removed.borrow().is_empty();
+
+        let path = match &self.path {
+            Some(path) if unsaved => path,
+            _ => return Ok(()),
+        };
+
+        let mut entries = read(path);
+        self.forget_other_boot(&mut entries);
+
+        let removed = self.removed.borrow();
+        entries.retain(|key, _| !removed.iter().any(|prefix| key.starts_with(prefix)));
+        drop(removed);
+
+        // Merged with the file as it is now, e.g. so a higher peak that
+        // another run saved since isn't lowered.
+        let merges = self.merges.borrow();
+
+        for (key, value) in self.changed.borrow().iter() {
+            let value = match (merges.get(key), entries.get(key)) {
+                (Some(merge), Some(saved)) => merge(saved, value),
+                _ => value.clone(),
+            };
+
+            entries.insert(key.clone(), value);
+        }
+
+        drop(merges);
+
+        let mut entries: Vec<(String, String)> = entries.into_iter().collect();
+        entries.sort();
+
+        let mut contents = String::new();
+
+        for (key, value) in entries {
+            contents += &format!("{}\t{}\n", key, value);
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+        }
+
+        // Written next to it and renamed over it, so that a run reading it
+        // at the same time never sees half of it.
+        let temporary = path.with_extension(format!("{}", std::process::id()));
+
+        fs::write(&temporary, contents)
+            .with_context(|| format!("Cannot write {}", temporary.display()))?;
+
+        fs::rename(&temporary, path).with_context(|| format!("Cannot write {}", path.display()))?;
+
+        // Kept until they're saved, so a failed save is tried again.
+        self.changed.borrow_mut().clear();
+        self.removed.borrow_mut().clear();
+        self.merges.borrow_mut().clear();
+
+        Ok(())
+    }
+}
+
+/// A missing or unreadable file is the same as an empty one.
+fn read(path: &Path) -> HashMap<String, String> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
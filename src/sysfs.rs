@@ -0,0 +1,65 @@
+use ah::Context;
+use anyhow as ah;
+use std::fs;
+
+use std::path::{Path, PathBuf};
+
+/// The hwmon class directory, relative to the sysfs root.
+pub const SYSFS_HWMON: &str = "class/hwmon";
+
+/// The thermal class directory, relative to the sysfs root.
+pub const SYSFS_THERMAL: &str = "class/thermal";
+
+/// The cpu device directory, relative to the sysfs root.
+pub const SYSFS_CPU: &str = "devices/system/cpu";
+
+/// A hwmon chip directory, e.g. hwmon3, and the name of its driver.
+pub struct HwmonDir {
+    pub number: u64,
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// The directories in path that are named prefix followed by a number, e.g.
+/// hwmon3 or cpu7, with that number. Others, like cpufreq next to the cpuN
+/// directories, are skipped. In numeric order, so that hwmon10 comes after
+/// hwmon2.
+pub fn numbered_dirs(path: &Path, prefix: &str) -> ah::Result<Vec<(u64, PathBuf)>> {
+    let dirs = fs::read_dir(path).with_context(|| format!("Cannot read {}", path.display()))?;
+
+    let mut dirs: Vec<(u64, PathBuf)> = dirs
+        .flatten()
+        .filter_map(|dir| {
+            let n = dir
+                .file_name()
+                .to_str()?
+                .strip_prefix(prefix)?
+                .parse::<u64>()
+                .ok()?;
+
+            Some((n, dir.path()))
+        })
+        .collect();
+
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Every hwmon chip, in numeric order, for both the CPU drivers and the
+/// other chips. Directories without a readable name file are skipped.
+pub fn hwmon_dirs(sysfs_root: &Path) -> ah::Result<Vec<HwmonDir>> {
+    let dirs = numbered_dirs(&sysfs_root.join(SYSFS_HWMON), "hwmon")?;
+
+    Ok(dirs
+        .into_iter()
+        .filter_map(|(number, path)| {
+            let name = fs::read_to_string(path.join("name")).ok()?;
+
+            Some(HwmonDir {
+                number,
+                name: name.trim().to_string(),
+                path,
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,237 @@
+use anyhow as ah;
+
+use crate::units::{FreqUnit, Rounding, Unit};
+
+/// Placeholder names and the segments they stand for. Every segment can be
+/// used by its long name, and the most common ones have a shorter alias.
+const PLACEHOLDERS: &[(&str, &str)] = &[
+    ("avg", "--avg"),
+    ("median", "--median"),
+    ("min", "--temp-min"),
+    ("max", "--temp-max"),
+    ("package", "--temp-package"),
+    ("crit", "--core-critical"),
+    ("alarm", "--core-alarm"),
+    ("cores", "--core-count"),
+    ("sockets", "--socket-count"),
+    ("zone", "--zone-temp"),
+    ("nl", "--newline"),
+    ("glyph", "--glyph"),
+    ("temp", "--temp"),
+    ("temp-min", "--temp-min"),
+    ("temp-max", "--temp-max"),
+    ("temp-package", "--temp-package"),
+    ("newline", "--newline"),
+    ("core-count", "--core-count"),
+    ("core-critical", "--core-critical"),
+    ("core-alarm", "--core-alarm"),
+    ("core-cpus", "--core-cpus"),
+    ("socket-count", "--socket-count"),
+    ("zone-temp", "--zone-temp"),
+    ("zone-passive", "--zone-passive"),
+    ("zone-hot", "--zone-hot"),
+    ("zone-critical", "--zone-critical"),
+    ("hwmon", "--hwmon"),
+    ("fan", "--fan"),
+    ("fan-min", "--fan-min"),
+    ("fan-max", "--fan-max"),
+    ("fan-target", "--fan-target"),
+    ("fan-pwm", "--fan-pwm"),
+    ("fan-fastest", "--fan-fastest"),
+    ("fan-slowest", "--fan-slowest"),
+    ("drive", "--drive"),
+    ("drive-min", "--drive-min"),
+    ("drive-max", "--drive-max"),
+    ("drive-critical", "--drive-critical"),
+    ("freq", "--freq"),
+    ("freq-hw-min", "--freq-hw-min"),
+    ("freq-hw-max", "--freq-hw-max"),
+    ("freq-avg", "--freq-avg"),
+    ("freq-median", "--freq-median"),
+    ("freq-min", "--freq-min"),
+    ("freq-max", "--freq-max"),
+    ("usage", "--usage"),
+    ("usage-user", "--usage-user"),
+    ("usage-system", "--usage-system"),
+    ("usage-iowait", "--usage-iowait"),
+    ("usage-steal", "--usage-steal"),
+    ("gauge", "--gauge"),
+    ("sparkline", "--sparkline"),
+    ("rule", "--rule"),
+    ("moving-avg", "--moving-avg"),
+    ("ema", "--ema"),
+    ("window-min", "--window-min"),
+    ("window-max", "--window-max"),
+    ("trend", "--trend"),
+    ("rate", "--rate"),
+    ("peak-max", "--peak-max"),
+    ("peak-min", "--peak-min"),
+    ("reset-peaks", "--reset-peaks"),
+];
+
+/// Keys that are never produced by parse_args, since they don't start with
+/// a dash, which save and restore the format settings around a placeholder
+/// that has options of its own.
+pub const PUSH_SETTINGS: &str = "push";
+pub const POP_SETTINGS: &str = "pop";
+
+/// Turns the options after the colon of a placeholder into the control flags
+/// that apply them, e.g. ".1,f,glyph" into -p 1 -tu f -ug yes.
+fn expand_options(placeholder: &str, options: &str) -> ah::Result<Vec<(String, Vec<String>)>> {
+    let mut flags = Vec::new();
+
+    for option in options.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+        let flag = if let Some(precision) = option.strip_prefix('.') {
+            match precision.parse::<usize>() {
+                Ok(_) => ("--precision", precision),
+                Err(_) => {
+                    return Err(ah::anyhow!(
+                        "Invalid precision '{}' in {{{}}}",
+                        option,
+                        placeholder
+                    ))
+                }
+            }
+        } else if let Some(width) = option.strip_prefix(['<', '>', '^']) {
+            match width.parse::<usize>() {
+                Ok(_) => {
+                    flags.push(("--align".to_string(), vec![option[..1].to_string()]));
+                    ("--width", width)
+                }
+                Err(_) => {
+                    return Err(ah::anyhow!(
+                        "Invalid width '{}' in {{{}}}",
+                        option,
+                        placeholder
+                    ))
+                }
+            }
+        } else if matches!(option, "g" | "glyph") {
+            ("--use-glyph", "yes")
+        } else if matches!(option, "nog" | "noglyph") {
+            ("--use-glyph", "no")
+        } else if option.to_lowercase().ends_with("hz") && FreqUnit::from_str(option).is_some() {
+            ("--freq-unit", option)
+        } else if Unit::from_str(option).is_some() {
+            ("--target-unit", option)
+        } else if Rounding::from_str(option).is_some() {
+            ("--rounding", option)
+        } else {
+            return Err(ah::anyhow!(
+                "Unknown option '{}' in {{{}}}",
+                option,
+                placeholder
+            ));
+        };
+
+        flags.push((flag.0.to_string(), vec![flag.1.to_string()]));
+    }
+
+    Ok(flags)
+}
+
+/// Turns one placeholder, without its braces, into the segment it stands
+/// for, e.g. "temp 0 1:.1" into --temp 0 1, with precision 1 for just it,
+/// and "temp 1:3:.1" into --temp 1:3, with precision 1 for just it.
+fn expand_placeholder(placeholder: &str) -> ah::Result<Vec<(String, Vec<String>)>> {
+    // Options follow the last colon, unless what follows it isn't a list of
+    // options, in which case the colon is part of an argument, like in
+    // {temp 1:3} or {zone acpitz:1}. Right after the name there are no
+    // arguments, so it's always options there, and mistakes in them show.
+    let (segment, options) = match placeholder.rsplit_once(':') {
+        Some((segment, options)) => match expand_options(placeholder, options) {
+            Ok(options) => (segment, Some(options)),
+            Err(_) if segment.trim().contains(char::is_whitespace) => (placeholder, None),
+            Err(e) => return Err(e),
+        },
+        None => (placeholder, None),
+    };
+
+    let mut words = segment.split_whitespace();
+
+    let name = words
+        .next()
+        .ok_or(ah::anyhow!("Empty placeholder {{{}}}", placeholder))?;
+
+    let key = PLACEHOLDERS
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, key)| key.to_string())
+        .ok_or(ah::anyhow!("Unknown placeholder {{{}}}", placeholder))?;
+
+    let segment = (key, words.map(String::from).collect());
+
+    match options {
+        Some(mut options) => {
+            let mut expanded = vec![(PUSH_SETTINGS.to_string(), Vec::new())];
+            expanded.append(&mut options);
+            expanded.push(segment);
+            expanded.push((POP_SETTINGS.to_string(), Vec::new()));
+            Ok(expanded)
+        }
+        None => Ok(vec![segment]),
+    }
+}
+
+/// Expands a --format template into the segment sequence it describes, so
+/// that '{avg} [{min}/{max}]' is evaluated exactly like
+/// -av -s ' [' -tm -s '/' -tx -s ']'. Braces are escaped by doubling them,
+/// as in {{ and }}.
+pub fn expand(template: &str) -> ah::Result<Vec<(String, Vec<String>)>> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.char_indices().peekable();
+
+    let column = |i: usize| template[..i].chars().count() + 1;
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' if chars.next_if(|(_, c)| *c == '{').is_some() => literal.push('{'),
+            '}' if chars.next_if(|(_, c)| *c == '}').is_some() => literal.push('}'),
+
+            '{' => {
+                let mut placeholder = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, '{')) => {
+                            return Err(ah::anyhow!(
+                                "Unexpected {{ inside the placeholder starting at column {}",
+                                column(i)
+                            ))
+                        }
+                        Some((_, c)) => placeholder.push(c),
+                        None => {
+                            return Err(ah::anyhow!(
+                                "Unclosed placeholder starting at column {}",
+                                column(i)
+                            ))
+                        }
+                    }
+                }
+
+                if !literal.is_empty() {
+                    segments.push(("--strings".to_string(), vec![std::mem::take(&mut literal)]));
+                }
+
+                segments.append(&mut expand_placeholder(&placeholder)?);
+            }
+
+            '}' => {
+                return Err(ah::anyhow!(
+                    "Unmatched }} at column {}, use }}}} for a literal }}",
+                    column(i)
+                ))
+            }
+
+            c => literal.push(c),
+        }
+    }
+
+    if !literal.is_empty() {
+        segments.push(("--strings".to_string(), vec![literal]));
+    }
+
+    Ok(segments)
+}
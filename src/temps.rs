@@ -2,196 +2,332 @@ use anyhow as ah;
 use std::collections::HashMap;
 use std::fs;
 
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
 
-const SYSFS_HWMON: &str = "/sys/class/hwmon";
+use crate::sysfs;
 
 #[derive(Debug, Clone, Copy)]
 enum Identifier {
     Core(u64),
-    Package,
+    // coretemp labels carry the socket's physical package id, AMD's don't.
+    Package(Option<u64>),
+    // AMD's Tctl is Tdie plus a fan control offset on some parts, so it only
+    // fills in for the package when there's no Tdie sensor.
+    Control,
 }
 
+/// The hwmon drivers that expose CPU temperatures, and how their labels map
+/// onto the package and core roles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Chip {
+    Intel,
+    Amd,
+}
+
+impl Chip {
+    fn from_name(name: &str) -> Option<Chip> {
+        match name {
+            "coretemp" => Some(Chip::Intel),
+            "k10temp" | "zenpower" => Some(Chip::Amd),
+            _ => None,
+        }
+    }
+
+    fn identify(&self, label: &str) -> Option<Identifier> {
+        let label_n = || {
+            label
+                .chars()
+                .filter(|c| c.is_ascii_digit())
+                .collect::<String>()
+                .parse::<u64>()
+                .ok()
+        };
+
+        match self {
+            Chip::Intel => {
+                if label.starts_with("package") {
+                    Some(Identifier::Package(label_n()))
+                } else if label.starts_with("core") {
+                    label_n().map(Identifier::Core)
+                } else {
+                    None
+                }
+            }
+
+            // Tccd labels count from 1, cores count from 0 like coretemp's.
+            Chip::Amd => match label {
+                "tdie" => Some(Identifier::Package(None)),
+                "tctl" => Some(Identifier::Control),
+                _ if label.starts_with("tccd") => label_n()
+                    .and_then(|n| n.checked_sub(1))
+                    .map(Identifier::Core),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Clone)]
 struct CoreSensor {
-    label_id: Identifier,
-    temp_id: u64,
-    temp_label: PathBuf,
     temp_input: PathBuf,
-    temp_crit: PathBuf,
-    temp_crit_alarm: PathBuf,
+    temp_crit: Option<PathBuf>,
+    temp_crit_alarm: Option<PathBuf>,
 }
 
 impl CoreSensor {
-    fn read_label(&self) -> ah::Result<String> {
-        let label = fs::read_to_string(&self.temp_label)?;
-        Ok(label.trim().to_string())
-    }
-
     fn read_input(&self) -> ah::Result<u64> {
         let input = fs::read_to_string(&self.temp_input)?;
         Ok(input.trim().parse::<u64>()?)
     }
 
     fn read_crit(&self) -> ah::Result<u64> {
-        let crit = fs::read_to_string(&self.temp_crit)?;
+        let temp_crit = self
+            .temp_crit
+            .as_ref()
+            .ok_or(ah::anyhow!("Sensor has no critical temperature"))?;
+
+        let crit = fs::read_to_string(temp_crit)?;
         Ok(crit.trim().parse::<u64>()?)
     }
 
     fn read_crit_alarm(&self) -> ah::Result<u64> {
-        let crit_alarm = fs::read_to_string(&self.temp_crit_alarm)?;
+        let temp_crit_alarm = self
+            .temp_crit_alarm
+            .as_ref()
+            .ok_or(ah::anyhow!("Sensor has no critical alarm"))?;
+
+        let crit_alarm = fs::read_to_string(temp_crit_alarm)?;
         Ok(crit_alarm.trim().parse::<u64>()?)
     }
 }
 
-pub struct CoreTemp {
+/// Addresses a core as SOCKET:CORE, where SOCKET is the physical package id
+/// and CORE is the core number from the sensor label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CoreId {
+    pub socket: u64,
+    pub core: u64,
+}
+
+impl CoreId {
+    /// Parses "SOCKET:CORE", or just "CORE", in which case the core is
+    /// looked up on the given default socket.
+    pub fn from_str(s: &str, default_socket: u64) -> Option<CoreId> {
+        match s.split_once(':') {
+            Some((socket, core)) => Some(CoreId {
+                socket: socket.parse::<u64>().ok()?,
+                core: core.parse::<u64>().ok()?,
+            }),
+            None => Some(CoreId {
+                socket: default_socket,
+                core: s.parse::<u64>().ok()?,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for CoreId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.socket, self.core)
+    }
+}
+
+struct Socket {
+    chip: Chip,
     package: CoreSensor,
     cores: HashMap<u64, CoreSensor>,
 }
 
+pub struct CoreTemp {
+    sockets: HashMap<u64, Socket>,
+}
+
 impl CoreTemp {
-    pub fn get_package(&self) -> ah::Result<u64> {
-        self.package.read_input()
+    fn socket(&self, socket: u64) -> ah::Result<&Socket> {
+        self.sockets
+            .get(&socket)
+            .ok_or(ah::anyhow!("Socket {} does not exist", socket))
     }
 
-    pub fn get_cores(&self) -> Vec<u64> {
-        self.cores.keys().copied().collect()
+    fn core(&self, core: CoreId) -> ah::Result<&CoreSensor> {
+        self.socket(core.socket)?
+            .cores
+            .get(&core.core)
+            .ok_or(ah::anyhow!("Core {} does not exist", core))
     }
 
-    pub fn get_count(&self) -> usize {
-        self.cores.len()
-    }
+    /// Reads every core on the given socket, or on all sockets if None.
+    fn read_inputs(&self, socket: Option<u64>) -> ah::Result<Vec<u64>> {
+        let mut temperatures = Vec::new();
 
-    pub fn get_temp(&self, core: u64) -> ah::Result<u64> {
-        let core = self
-            .cores
-            .get(&core)
-            .ok_or(ah::anyhow!("Core {} does not exist", core))?;
+        for core in self.get_cores(socket)? {
+            temperatures.push(self.core(core)?.read_input()?);
+        }
+
+        if temperatures.is_empty() {
+            return Err(ah::anyhow!("No core sensors found"));
+        }
 
-        core.read_input()
+        Ok(temperatures)
     }
 
-    pub fn get_critical(&self, core: u64) -> ah::Result<u64> {
-        let core = self
-            .cores
-            .get(&core)
-            .ok_or(ah::anyhow!("Core {} does not exist", core))?;
+    pub fn get_sockets(&self) -> Vec<u64> {
+        let mut sockets: Vec<u64> = self.sockets.keys().copied().collect();
+        sockets.sort();
+        sockets
+    }
 
-        core.read_crit()
+    /// Whether the cores of a socket are physical cores, numbered by the
+    /// same core ids as the CPU topology. AMD's "cores" are CCDs instead,
+    /// which each hold several physical cores.
+    pub fn has_physical_cores(&self, socket: u64) -> bool {
+        self.socket(socket)
+            .is_ok_and(|socket| socket.chip == Chip::Intel)
     }
 
-    pub fn get_critical_alarm(&self, core: u64) -> ah::Result<u64> {
-        let core = self
-            .cores
-            .get(&core)
-            .ok_or(ah::anyhow!("Core {} does not exist", core))?;
+    /// The socket that cores and packages are looked up on when no socket is
+    /// given, which on single socket machines is the only one.
+    pub fn get_default_socket(&self) -> u64 {
+        self.get_sockets().first().copied().unwrap_or(0)
+    }
 
-        core.read_crit_alarm()
+    pub fn get_socket_count(&self) -> usize {
+        self.sockets.len()
     }
 
-    pub fn get_temps_for(&self, cores: &Vec<u64>) -> ah::Result<Vec<u64>> {
-        let mut temps = Vec::new();
-        for core_n in cores {
-            temps.push(self.get_temp(*core_n)?);
-        }
-        Ok(temps)
+    pub fn get_package(&self, socket: u64) -> ah::Result<u64> {
+        self.socket(socket)?.package.read_input()
     }
 
-    pub fn get_average(&self) -> ah::Result<u64> {
-        let mut sum: u64 = 0;
+    pub fn get_package_critical(&self, socket: u64) -> ah::Result<u64> {
+        self.socket(socket)?.package.read_crit()
+    }
 
-        for (_, core) in &self.cores {
-            sum += core.read_input()?;
+    /// The critical temperature that aggregates of the given socket, or of
+    /// all sockets if None, are held against, which is the lowest of the
+    /// package critical temperatures.
+    pub fn get_socket_critical(&self, socket: Option<u64>) -> ah::Result<u64> {
+        let sockets = match socket {
+            Some(socket) => vec![socket],
+            None => self.get_sockets(),
+        };
+
+        let mut crits = Vec::new();
+
+        for socket in sockets {
+            crits.push(self.get_package_critical(socket)?);
         }
 
-        Ok(sum / self.cores.len() as u64)
+        crits
+            .into_iter()
+            .min()
+            .ok_or(ah::anyhow!("No package sensor found"))
     }
 
-    pub fn get_median(&self) -> ah::Result<u64> {
-        let mut temperatures: Vec<u64> = Vec::with_capacity(self.cores.len());
+    /// Every core on the given socket, or on all sockets if None, ordered by
+    /// socket and then core.
+    pub fn get_cores(&self, socket: Option<u64>) -> ah::Result<Vec<CoreId>> {
+        let sockets = match socket {
+            Some(socket) => {
+                self.socket(socket)?;
+                vec![socket]
+            }
+            None => self.get_sockets(),
+        };
 
-        for (_, core) in &self.cores {
-            let temperature = core.read_input()?;
-            temperatures.push(temperature);
-        }
+        let mut cores = Vec::new();
 
-        temperatures.sort();
+        for socket in sockets {
+            let mut socket_cores: Vec<CoreId> = self.sockets[&socket]
+                .cores
+                .keys()
+                .map(|&core| CoreId { socket, core })
+                .collect();
 
-        let median = if temperatures.len() % 2 == 0 {
-            let center = temperatures.len() / 2;
+            socket_cores.sort();
+            cores.append(&mut socket_cores);
+        }
 
-            (temperatures[center] + temperatures[center + 1]) / 2
-        } else {
-            temperatures[temperatures.len() / 2]
-        };
+        Ok(cores)
+    }
 
-        Ok(median)
+    pub fn get_count(&self, socket: Option<u64>) -> ah::Result<usize> {
+        Ok(self.get_cores(socket)?.len())
     }
 
-    pub fn get_min(&self) -> ah::Result<u64> {
-        let mut min = u64::MAX;
+    pub fn get_temp(&self, core: CoreId) -> ah::Result<u64> {
+        self.core(core)?.read_input()
+    }
 
-        for (_, core) in &self.cores {
-            let temp = core.read_input()?;
+    pub fn get_critical(&self, core: CoreId) -> ah::Result<u64> {
+        self.core(core)?.read_crit()
+    }
 
-            if temp < min {
-                min = temp;
-            }
-        }
+    pub fn get_critical_alarm(&self, core: CoreId) -> ah::Result<u64> {
+        self.core(core)?.read_crit_alarm()
+    }
 
-        Ok(min)
+    pub fn get_average(&self, socket: Option<u64>) -> ah::Result<f64> {
+        let temperatures = self.read_inputs(socket)?;
+        Ok(temperatures.iter().sum::<u64>() as f64 / temperatures.len() as f64)
     }
 
-    pub fn get_max(&self) -> ah::Result<u64> {
-        let mut max = u64::MIN;
+    pub fn get_median(&self, socket: Option<u64>) -> ah::Result<f64> {
+        let mut temperatures = self.read_inputs(socket)?;
 
-        for (_, core) in &self.cores {
-            let temp = core.read_input()?;
+        temperatures.sort();
 
-            if temp > max {
-                max = temp;
-            }
-        }
+        let median = if temperatures.len().is_multiple_of(2) {
+            let center = temperatures.len() / 2;
+
+            (temperatures[center - 1] + temperatures[center]) as f64 / 2.0
+        } else {
+            temperatures[temperatures.len() / 2] as f64
+        };
 
-        Ok(max)
+        Ok(median)
     }
 
-    pub fn try_new() -> ah::Result<CoreTemp> {
-        let hwmon_dirs = fs::read_dir(SYSFS_HWMON)?;
+    pub fn get_min(&self, socket: Option<u64>) -> ah::Result<u64> {
+        let temperatures = self.read_inputs(socket)?;
+        Ok(temperatures.into_iter().min().unwrap_or(u64::MAX))
+    }
 
-        let mut package: Option<CoreSensor> = None;
-        let mut cores = HashMap::<u64, CoreSensor>::new();
+    pub fn get_max(&self, socket: Option<u64>) -> ah::Result<u64> {
+        let temperatures = self.read_inputs(socket)?;
+        Ok(temperatures.into_iter().max().unwrap_or(u64::MIN))
+    }
 
-        for dir in hwmon_dirs {
-            let dir = match dir {
-                Ok(dir) => dir,
-                Err(_) => continue,
-            };
+    pub fn try_new(sysfs_root: &Path) -> ah::Result<CoreTemp> {
+        // In order, so that sockets without a package id in their labels
+        // (AMD) are numbered consistently between runs.
+        let hwmon_dirs = sysfs::hwmon_dirs(sysfs_root)?;
 
-            let dir_path = dir.path();
-            let name_file = dir_path.join("name");
+        let mut sockets = HashMap::<u64, Socket>::new();
+        let mut unnumbered = Vec::<Socket>::new();
 
-            // Skip directories with no name file.
-            if !name_file.exists() {
-                continue;
-            }
+        for hwmon_dir in hwmon_dirs {
+            let dir_path = hwmon_dir.path;
 
-            let name = match fs::read_to_string(&name_file) {
-                Ok(name) => name,
-                Err(_) => continue,
+            // Skip directories whose name isn't one of the CPU drivers.
+            let chip = match Chip::from_name(&hwmon_dir.name) {
+                Some(chip) => chip,
+                None => continue,
             };
 
-            // Skip directories without a name file containing "coretemp"
-            if name.trim() != "coretemp" {
-                continue;
-            }
+            // Each hwmon directory of a CPU driver belongs to one socket.
+            let mut package: Option<CoreSensor> = None;
+            let mut package_id: Option<u64> = None;
+            let mut control: Option<CoreSensor> = None;
+            let mut cores = HashMap::<u64, CoreSensor>::new();
 
             let entries = fs::read_dir(&dir_path)?;
             let mut temp_ids_seen = Vec::<u64>::new();
-
             // Try to collect all tempN_xyz files for unque N, into the map,
             // where xyz is label, input, crit, crit_alarm. Do this by looking
             // for tempN_label files, and using its N to infer the paths of
-            // input, crit, and crit_alarm files.
+            // input, crit, and crit_alarm files. Only input is mandatory,
+            // k10temp and zenpower have no crit files.
 
             for entry in entries.flatten() {
                 let entry_path = entry.path();
@@ -230,27 +366,10 @@ impl CoreTemp {
 
                 let label = fs::read_to_string(&entry_path)?.trim().to_lowercase();
 
-                let label_id: Identifier;
-
-                if label.starts_with("package") {
-                    label_id = Identifier::Package;
-                } else if label.starts_with("core") {
-                    let core_n = match label
-                        .chars()
-                        .filter(|c| c.is_ascii_digit())
-                        .collect::<String>()
-                        .parse::<u64>()
-                    {
-                        Ok(n) => n,
-                        Err(_) => continue,
-                    };
-
-                    label_id = Identifier::Core(core_n);
-                } else {
-                    continue;
-                }
-
-                let temp_label = entry_path.clone();
+                let label_id = match chip.identify(&label) {
+                    Some(label_id) => label_id,
+                    None => continue,
+                };
 
                 let temp_input = entry_path
                     .clone()
@@ -264,17 +383,14 @@ impl CoreTemp {
                     .clone()
                     .with_file_name(format!("temp{}_crit_alarm", temp_id));
 
-                if !temp_input.exists() || !temp_crit.exists() || !temp_crit_alarm.exists() {
+                if !temp_input.exists() {
                     continue;
                 }
 
                 let sensor = CoreSensor {
-                    label_id,
-                    temp_id,
-                    temp_label,
                     temp_input,
-                    temp_crit,
-                    temp_crit_alarm,
+                    temp_crit: temp_crit.exists().then_some(temp_crit),
+                    temp_crit_alarm: temp_crit_alarm.exists().then_some(temp_crit_alarm),
                 };
 
                 match label_id {
@@ -286,23 +402,67 @@ impl CoreTemp {
                         cores.insert(core_n, sensor);
                     }
 
-                    Identifier::Package => {
+                    Identifier::Package(id) => {
                         if package.is_some() {
                             continue;
                         }
 
                         package = Some(sensor);
+                        package_id = id;
+                    }
+
+                    Identifier::Control => {
+                        if control.is_some() {
+                            continue;
+                        }
+
+                        control = Some(sensor);
                     }
                 }
 
                 temp_ids_seen.push(temp_id);
             }
+
+            let package = match package.or(control) {
+                Some(package) => package,
+                None => continue,
+            };
+
+            // Single CCD parts and older AMD families only report Tctl, so the
+            // package sensor stands in as the only core.
+            if cores.is_empty() {
+                cores.insert(0, package.clone());
+            }
+
+            let socket = Socket {
+                chip,
+                package,
+                cores,
+            };
+
+            match package_id {
+                Some(id) if !sockets.contains_key(&id) => {
+                    sockets.insert(id, socket);
+                }
+                _ => unnumbered.push(socket),
+            }
+        }
+
+        // Sockets without a package id take the lowest free ids, in order.
+        let mut next_id = 0;
+
+        for socket in unnumbered {
+            while sockets.contains_key(&next_id) {
+                next_id += 1;
+            }
+
+            sockets.insert(next_id, socket);
         }
 
-        match package {
-            Some(package) => Ok(CoreTemp { package, cores }),
-            None => Err(ah::anyhow!("No package sensor found")),
+        if sockets.is_empty() {
+            return Err(ah::anyhow!("No package sensor found"));
         }
 
+        Ok(CoreTemp { sockets })
     }
 }
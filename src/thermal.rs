@@ -0,0 +1,128 @@
+use anyhow as ah;
+use std::fs;
+
+use std::path::{Path, PathBuf};
+
+use crate::sysfs::{self, SYSFS_THERMAL};
+
+struct TripPoint {
+    trip_type: String,
+    trip_temp: PathBuf,
+}
+
+struct ThermalZone {
+    zone_name: String,
+    zone_type: String,
+    temp: PathBuf,
+    trip_points: Vec<TripPoint>,
+}
+
+impl ThermalZone {
+    // Millidegrees are signed, as zones like outdoor sensors go below 0°C.
+    fn read_temp(&self) -> ah::Result<i64> {
+        let temp = fs::read_to_string(&self.temp)?;
+        Ok(temp.trim().parse::<i64>()?)
+    }
+
+    fn read_trip(&self, trip_type: &str) -> ah::Result<i64> {
+        let trip_point = self
+            .trip_points
+            .iter()
+            .find(|trip_point| trip_point.trip_type == trip_type)
+            .ok_or(ah::anyhow!(
+                "Zone {} has no {} trip point",
+                self.zone_type,
+                trip_type
+            ))?;
+
+        let temp = fs::read_to_string(&trip_point.trip_temp)?;
+        Ok(temp.trim().parse::<i64>()?)
+    }
+}
+
+pub struct ThermalZones {
+    zones: Vec<ThermalZone>,
+}
+
+impl ThermalZones {
+    /// Looks up a zone by its type (e.g. x86_pkg_temp, cpu-thermal, acpitz),
+    /// or by its directory name (e.g. thermal_zone3). When several zones
+    /// share a type, TYPE:N picks the Nth of them, counting from 0.
+    fn zone(&self, zone: &str) -> ah::Result<&ThermalZone> {
+        let (zone_type, nth) = match zone.rsplit_once(':') {
+            Some((zone_type, nth)) => match nth.parse::<usize>() {
+                Ok(nth) => (zone_type, nth),
+                Err(_) => (zone, 0),
+            },
+            None => (zone, 0),
+        };
+
+        self.zones
+            .iter()
+            .filter(|z| z.zone_type == zone_type)
+            .nth(nth)
+            .or_else(|| self.zones.iter().find(|z| z.zone_name == zone))
+            .ok_or(ah::anyhow!("Thermal zone {} does not exist", zone))
+    }
+
+    pub fn get_temp(&self, zone: &str) -> ah::Result<i64> {
+        self.zone(zone)?.read_temp()
+    }
+
+    /// Reads the first trip point of the given type, which is one of
+    /// passive, hot, critical, or active.
+    pub fn get_trip(&self, zone: &str, trip_type: &str) -> ah::Result<i64> {
+        self.zone(zone)?.read_trip(trip_type)
+    }
+
+    pub fn try_new(sysfs_root: &Path) -> ah::Result<ThermalZones> {
+        let zone_dirs = sysfs::numbered_dirs(&sysfs_root.join(SYSFS_THERMAL), "thermal_zone")?;
+
+        let mut zones = Vec::<ThermalZone>::new();
+
+        for (zone_n, dir_path) in zone_dirs {
+            let zone_type = match fs::read_to_string(dir_path.join("type")) {
+                Ok(zone_type) => zone_type.trim().to_string(),
+                Err(_) => continue,
+            };
+
+            let temp = dir_path.join("temp");
+
+            if !temp.exists() {
+                continue;
+            }
+
+            // Trip points are numbered from 0 without gaps, each with a
+            // trip_point_N_type and trip_point_N_temp file.
+            let mut trip_points = Vec::<TripPoint>::new();
+
+            for trip_n in 0.. {
+                let trip_type = dir_path.join(format!("trip_point_{}_type", trip_n));
+                let trip_temp = dir_path.join(format!("trip_point_{}_temp", trip_n));
+
+                let trip_type = match fs::read_to_string(&trip_type) {
+                    Ok(trip_type) => trip_type.trim().to_string(),
+                    Err(_) => break,
+                };
+
+                trip_points.push(TripPoint {
+                    trip_type,
+                    trip_temp,
+                });
+            }
+
+            zones.push(ThermalZone {
+                zone_name: format!("thermal_zone{}", zone_n),
+                zone_type,
+                temp,
+                trip_points,
+            });
+        }
+
+        if zones.is_empty() {
+            return Err(ah::anyhow!("No thermal zones found"));
+        }
+
+        Ok(ThermalZones { zones })
+    }
+}
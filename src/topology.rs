@@ -0,0 +1,68 @@
+use anyhow as ah;
+use std::collections::HashMap;
+use std::fs;
+
+use std::path::Path;
+
+use crate::sysfs::{self, SYSFS_CPU};
+use crate::temps::CoreId;
+
+fn read_id(path: &Path) -> ah::Result<u64> {
+    let id = fs::read_to_string(path)?;
+    Ok(id.trim().parse::<u64>()?)
+}
+
+/// Which physical core, on which socket, each logical CPU belongs to. These
+/// are the same core and package ids coretemp labels its sensors with.
+pub struct Topology {
+    cpus: HashMap<u64, CoreId>,
+}
+
+impl Topology {
+    pub fn get_core(&self, cpu: u64) -> ah::Result<CoreId> {
+        self.cpus
+            .get(&cpu)
+            .copied()
+            .ok_or(ah::anyhow!("CPU {} has no topology", cpu))
+    }
+
+    /// The logical CPUs (hyperthreads) that share the given core.
+    pub fn get_siblings(&self, core: CoreId) -> Vec<u64> {
+        let mut siblings: Vec<u64> = self
+            .cpus
+            .iter()
+            .filter(|(_, c)| **c == core)
+            .map(|(cpu, _)| *cpu)
+            .collect();
+
+        siblings.sort();
+        siblings
+    }
+
+    pub fn try_new(sysfs_root: &Path) -> ah::Result<Topology> {
+        let cpu_path = sysfs_root.join(SYSFS_CPU);
+
+        let mut cpus = HashMap::<u64, CoreId>::new();
+
+        for (cpu_n, dir_path) in sysfs::numbered_dirs(&cpu_path, "cpu")? {
+            // Offline CPUs have no topology directory.
+            let topology = dir_path.join("topology");
+
+            let (socket, core) = match (
+                read_id(&topology.join("physical_package_id")),
+                read_id(&topology.join("core_id")),
+            ) {
+                (Ok(socket), Ok(core)) => (socket, core),
+                _ => continue,
+            };
+
+            cpus.insert(cpu_n, CoreId { socket, core });
+        }
+
+        if cpus.is_empty() {
+            return Err(ah::anyhow!("No CPUs with topology found"));
+        }
+
+        Ok(Topology { cpus })
+    }
+}
@@ -1,3 +1,4 @@
+#[derive(Clone)]
 pub enum Unit {
     Celcius,
     Fahrenheit,
@@ -37,6 +38,7 @@ impl Unit {
         }
     }
 
+    #[allow(dead_code)]
     pub fn to_str_long(&self) -> &'static str {
         match self {
             Unit::Celcius => "Celcius",
@@ -45,6 +47,7 @@ impl Unit {
         }
     }
 
+    #[allow(dead_code)]
     pub fn to_str_short(&self) -> &'static str {
         match self {
             Unit::Celcius => "C",
@@ -62,3 +65,107 @@ impl Unit {
         }
     }
 }
+
+/// Converts a temperature from millidegrees of the base unit to the unit
+/// it's printed in, which is the target unit if one is set.
+pub fn final_temp(millis: f64, base_unit: &Unit, target_unit: Option<&Unit>) -> f64 {
+    match target_unit {
+        Some(target_unit) => base_unit.convert_to(target_unit, millis / 1000.0),
+        None => millis / 1000.0,
+    }
+}
+
+#[derive(Clone)]
+pub enum FreqUnit {
+    Kilohertz,
+    Megahertz,
+    Gigahertz,
+}
+
+impl FreqUnit {
+    /// Converts from kHz, which is what cpufreq reports frequencies in.
+    pub fn convert_khz(&self, khz: f64) -> f64 {
+        match self {
+            FreqUnit::Kilohertz => khz,
+            FreqUnit::Megahertz => khz / 1000.0,
+            FreqUnit::Gigahertz => khz / 1_000_000.0,
+        }
+    }
+
+    /// The number of decimal places that's sensible for the unit when no
+    /// precision is set, since whole MHz are already plenty precise.
+    pub fn default_precision(&self) -> usize {
+        match self {
+            FreqUnit::Kilohertz | FreqUnit::Megahertz => 0,
+            FreqUnit::Gigahertz => 2,
+        }
+    }
+
+    pub fn to_str_short(&self) -> &'static str {
+        match self {
+            FreqUnit::Kilohertz => "kHz",
+            FreqUnit::Megahertz => "MHz",
+            FreqUnit::Gigahertz => "GHz",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<FreqUnit> {
+        match s {
+            "khz" | "kHz" | "KHz" | "k" => Some(FreqUnit::Kilohertz),
+            "mhz" | "MHz" | "m" | "M" => Some(FreqUnit::Megahertz),
+            "ghz" | "GHz" | "g" | "G" => Some(FreqUnit::Gigahertz),
+            _ => None,
+        }
+    }
+}
+
+/// How values are rounded to the number of decimal places they're printed
+/// with, when not leaving it up to the formatter.
+#[derive(Clone)]
+pub enum Rounding {
+    Floor,
+    Ceil,
+    Nearest,
+    HalfEven,
+    Truncate,
+}
+
+impl Rounding {
+    pub fn round(&self, value: f64, precision: usize) -> f64 {
+        let scale = 10f64.powi(i32::try_from(precision).unwrap_or(i32::MAX));
+        let mut scaled = value * scale;
+
+        // So many decimal places are beyond what an f64 holds, so there's
+        // nothing left to round, and scaling up would overflow.
+        if !scaled.is_finite() {
+            return value;
+        }
+
+        // Values like 1.1 * 100 come out as 110.00000000000001, which would
+        // ceil to 111, so anything that close to a whole number is one.
+        if (scaled - scaled.round()).abs() < 1e-9 {
+            scaled = scaled.round();
+        }
+
+        let rounded = match self {
+            Rounding::Floor => scaled.floor(),
+            Rounding::Ceil => scaled.ceil(),
+            Rounding::Nearest => scaled.round(),
+            Rounding::HalfEven => scaled.round_ties_even(),
+            Rounding::Truncate => scaled.trunc(),
+        };
+
+        rounded / scale
+    }
+
+    pub fn from_str(s: &str) -> Option<Rounding> {
+        match s {
+            "floor" | "down" => Some(Rounding::Floor),
+            "ceil" | "up" => Some(Rounding::Ceil),
+            "nearest" | "round" => Some(Rounding::Nearest),
+            "half-even" | "even" | "bankers" => Some(Rounding::HalfEven),
+            "truncate" | "trunc" => Some(Rounding::Truncate),
+            _ => None,
+        }
+    }
+}
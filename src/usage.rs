@@ -0,0 +1,201 @@
+use ah::Context;
+use anyhow as ah;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+use std::path::{Path, PathBuf};
+
+/// The kernel's CPU time accounting, relative to the procfs root.
+const PROCFS_STAT: &str = "stat";
+
+#[derive(Debug, Clone, Copy)]
+pub enum UsageKind {
+    Total,
+    User,
+    System,
+    Iowait,
+    Steal,
+}
+
+/// The time spent in each state, in USER_HZ ticks, from one cpu line of
+/// /proc/stat. Guest time is already counted in user and nice.
+#[derive(Debug, Clone, Copy, Default)]
+struct CpuTimes {
+    user: u64,
+    nice: u64,
+    system: u64,
+    idle: u64,
+    iowait: u64,
+    irq: u64,
+    softirq: u64,
+    steal: u64,
+}
+
+impl CpuTimes {
+    fn total(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.iowait
+            + self.irq
+            + self.softirq
+            + self.steal
+    }
+
+    // Counters can go backwards when a CPU is hotplugged, hence saturating.
+    fn since(&self, earlier: &CpuTimes) -> CpuTimes {
+        CpuTimes {
+            user: self.user.saturating_sub(earlier.user),
+            nice: self.nice.saturating_sub(earlier.nice),
+            system: self.system.saturating_sub(earlier.system),
+            idle: self.idle.saturating_sub(earlier.idle),
+            iowait: self.iowait.saturating_sub(earlier.iowait),
+            irq: self.irq.saturating_sub(earlier.irq),
+            softirq: self.softirq.saturating_sub(earlier.softirq),
+            steal: self.steal.saturating_sub(earlier.steal),
+        }
+    }
+
+    fn percent(&self, kind: UsageKind) -> f64 {
+        let part = match kind {
+            UsageKind::Total => self.total() - self.idle - self.iowait,
+            UsageKind::User => self.user + self.nice,
+            UsageKind::System => self.system + self.irq + self.softirq,
+            UsageKind::Iowait => self.iowait,
+            UsageKind::Steal => self.steal,
+        };
+
+        match self.total() {
+            0 => 0.0,
+            total => part as f64 * 100.0 / total as f64,
+        }
+    }
+}
+
+/// The cpu lines of /proc/stat, keyed by CPU number, or None for the line
+/// that sums up all CPUs.
+type Sample = HashMap<Option<u64>, CpuTimes>;
+
+fn parse_stat(stat: &str) -> Sample {
+    let mut sample = Sample::new();
+
+    for line in stat.lines() {
+        let mut fields = line.split_whitespace();
+
+        let cpu = match fields.next().and_then(|name| name.strip_prefix("cpu")) {
+            Some("") => None,
+            Some(n) => match n.parse::<u64>() {
+                Ok(n) => Some(n),
+                Err(_) => continue,
+            },
+            None => continue,
+        };
+
+        let ticks: Vec<u64> = fields.filter_map(|f| f.parse::<u64>().ok()).collect();
+        let tick = |i: usize| ticks.get(i).copied().unwrap_or(0);
+
+        sample.insert(
+            cpu,
+            CpuTimes {
+                user: tick(0),
+                nice: tick(1),
+                system: tick(2),
+                idle: tick(3),
+                iowait: tick(4),
+                irq: tick(5),
+                softirq: tick(6),
+                steal: tick(7),
+            },
+        );
+    }
+
+    sample
+}
+
+/// CPU utilization over the time between two samples of /proc/stat. The
+/// first tick samples twice, an interval apart, and every tick after that
+/// compares against the previous tick's sample instead of waiting again.
+pub struct CpuUsage {
+    stat: PathBuf,
+    previous: RefCell<Option<Sample>>,
+    elapsed: RefCell<Option<Sample>>,
+}
+
+impl CpuUsage {
+    pub fn new(procfs_root: &Path) -> CpuUsage {
+        CpuUsage {
+            stat: procfs_root.join(PROCFS_STAT),
+            previous: RefCell::new(None),
+            elapsed: RefCell::new(None),
+        }
+    }
+
+    fn sample(&self) -> ah::Result<Sample> {
+        let stat = fs::read_to_string(&self.stat)
+            .with_context(|| format!("Cannot read {}", self.stat.display()))?;
+
+        Ok(parse_stat(&stat))
+    }
+
+    /// Starts a new tick, so that the next usage segment takes a new sample
+    /// rather than reusing the elapsed times of the last one.
+    pub fn tick(&self) {
+        self.elapsed.borrow_mut().take();
+    }
+
+    /// The CPU times elapsed during this tick, sampled on first use so that
+    /// every usage segment in a sequence reports on the same time span.
+    fn elapsed(&self, interval: Duration) -> ah::Result<Sample> {
+        if let Some(elapsed) = self.elapsed.borrow().as_ref() {
+            return Ok(elapsed.clone());
+        }
+
+        let previous = match self.previous.take() {
+            Some(previous) => previous,
+            None => {
+                let first = self.sample()?;
+                std::thread::sleep(interval);
+                first
+            }
+        };
+
+        let current = self.sample()?;
+
+        let elapsed: Sample = current
+            .iter()
+            .filter_map(|(cpu, times)| Some((*cpu, times.since(previous.get(cpu)?))))
+            .collect();
+
+        self.previous.replace(Some(current));
+        self.elapsed.replace(Some(elapsed.clone()));
+
+        Ok(elapsed)
+    }
+
+    pub fn get_cpus(&self, interval: Duration) -> ah::Result<Vec<u64>> {
+        let mut cpus: Vec<u64> = self.elapsed(interval)?.keys().flatten().copied().collect();
+        cpus.sort();
+        Ok(cpus)
+    }
+
+    /// The percentage of time the given CPU, or all CPUs if None, spent in
+    /// the state described by kind.
+    pub fn get_usage(
+        &self,
+        cpu: Option<u64>,
+        kind: UsageKind,
+        interval: Duration,
+    ) -> ah::Result<f64> {
+        let elapsed = self.elapsed(interval)?;
+
+        let times = elapsed.get(&cpu).ok_or(ah::anyhow!(
+            "CPU {} does not exist",
+            cpu.map(|cpu| cpu.to_string()).unwrap_or_default()
+        ))?;
+
+        Ok(times.percent(kind))
+    }
+}
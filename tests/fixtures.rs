@@ -0,0 +1,1373 @@
+//! Runs the binary against the fake sysfs trees in tests/fixtures.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+fn coretempf(name: &str, args: &[&str]) -> String {
+    coretempf_at(&fixture(name), None, args)
+}
+
+/// coretempf on any sysfs tree, with state kept in runtime_dir, or not at
+/// all, so that tests never touch the real XDG_RUNTIME_DIR.
+fn command(root: &Path, runtime_dir: Option<&Path>, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_coretempf"));
+
+    command
+        .arg("--sysfs-root")
+        .arg(root)
+        .args(args)
+        .env_remove("CORETEMPF_SYSFS_ROOT")
+        .env_remove("XDG_RUNTIME_DIR");
+
+    if let Some(runtime_dir) = runtime_dir {
+        command.env("XDG_RUNTIME_DIR", runtime_dir);
+    }
+
+    command
+}
+
+/// Runs coretempf on any sysfs tree, see command.
+fn coretempf_at(root: &Path, runtime_dir: Option<&Path>, args: &[&str]) -> String {
+    let output = command(root, runtime_dir, args)
+        .output()
+        .expect("failed to run coretempf");
+
+    assert!(
+        output.status.success(),
+        "coretempf {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .trim_end()
+        .to_string()
+}
+
+/// Runs coretempf on a fixture, expecting it to fail, and returns its stderr.
+fn coretempf_fails(name: &str, args: &[&str]) -> String {
+    let output = command(&fixture(name), None, args)
+        .output()
+        .expect("failed to run coretempf");
+
+    assert!(!output.status.success(), "coretempf {:?} succeeded", args);
+    String::from_utf8(output.stderr).unwrap()
+}
+
+/// Copies a fixture to a temporary directory, for tests that change sensor
+/// readings while coretempf runs.
+fn scratch_fixture(name: &str, test: &str) -> PathBuf {
+    fn copy(from: &Path, to: &Path) {
+        fs::create_dir_all(to).unwrap();
+
+        for entry in fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+
+            if entry.file_type().unwrap().is_dir() {
+                copy(&entry.path(), &to.join(entry.file_name()));
+            } else {
+                fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+            }
+        }
+    }
+
+    let scratch = std::env::temp_dir().join(format!("coretempf-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&scratch);
+    copy(&fixture(name), &scratch);
+    scratch
+}
+
+/// Starts coretempf on any sysfs tree, see command, for tests that read the
+/// lines of a repeating mode as they're printed.
+fn spawn_coretempf(root: &Path, runtime_dir: Option<&Path>, args: &[&str]) -> Child {
+    command(root, runtime_dir, args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run coretempf")
+}
+
+/// Adds a thermal zone of type gate to a sysfs tree, whose temp is a named
+/// pipe. Reading it waits for pass_gate, so a -zt gate segment holds up
+/// each tick of a repeating run until the test lets it through, rather than
+/// the test racing the interval to change readings between ticks.
+fn add_gate(root: &Path) -> PathBuf {
+    let zone = root.join("class/thermal/thermal_zone0");
+    fs::create_dir_all(&zone).unwrap();
+    fs::write(zone.join("type"), "gate\n").unwrap();
+
+    let gate = zone.join("temp");
+    assert!(Command::new("mkfifo")
+        .arg(&gate)
+        .status()
+        .unwrap()
+        .success());
+    gate
+}
+
+/// Waits for a run to reach the gate, makes a change, and then lets the
+/// rest of that tick through, which reads the gate as 0.
+fn pass_gate(gate: &Path, change: impl FnOnce()) {
+    let mut pipe = fs::OpenOptions::new().write(true).open(gate).unwrap();
+    change();
+    pipe.write_all(b"0\n").unwrap();
+}
+
+#[test]
+fn intel_desktop() {
+    assert_eq!(
+        coretempf("intel-desktop", &["-t", "all"]),
+        "45.00, 46.00, 47.00, 48.00, 49.00, 50.00"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-tp", "-s", " ", "-av", "-s", " ", "-md", "-s", " ", "-tm", "-s", " ", "-tx"]
+        ),
+        "49.00 47.50 47.50 45.00 50.00"
+    );
+
+    assert_eq!(
+        coretempf("intel-desktop", &["-cC", "0", "-s", " ", "-ca", "0"]),
+        "100.00 Core 1: false"
+    );
+    assert_eq!(coretempf("intel-desktop", &["-cc"]), "6");
+}
+
+#[test]
+fn amd_k10temp() {
+    assert_eq!(coretempf("amd-k10temp", &["-tp"]), "51.25");
+    assert_eq!(coretempf("amd-k10temp", &["-t", "0", "1"]), "55.00, 49.50");
+    assert_eq!(
+        coretempf("amd-k10temp", &["-tm", "-s", " ", "-tx"]),
+        "49.50 55.00"
+    );
+    assert_eq!(coretempf("amd-k10temp", &["-cC", "0"]), "N/A");
+
+    // The "cores" of k10temp are CCDs, which logical CPUs can't be mapped to.
+    assert!(coretempf_fails("amd-k10temp", &["-t", "cpu1"])
+        .contains("CPU 1 can't be mapped to a core sensor"));
+    assert_eq!(coretempf("amd-k10temp", &["-cs", "0", "1"]), "N/A, N/A");
+}
+
+#[test]
+fn xeon_two_sockets() {
+    assert_eq!(
+        coretempf("xeon-2s", &["-t", "all"]),
+        "40.00, 41.00, 42.00, 43.00, 50.00, 51.00, 52.00, 53.00"
+    );
+
+    assert_eq!(coretempf("xeon-2s", &["-t", "1:4", "4"]), "53.00, 43.00");
+    assert_eq!(
+        coretempf("xeon-2s", &["-t", "1:all"]),
+        "50.00, 51.00, 52.00, 53.00"
+    );
+    assert_eq!(
+        coretempf("xeon-2s", &["-tp", "-s", " ", "-tp", "1"]),
+        "44.00 54.00"
+    );
+
+    assert_eq!(
+        coretempf(
+            "xeon-2s",
+            &["-av", "-s", " ", "-av", "0", "-s", " ", "-av", "1"]
+        ),
+        "46.50 41.50 51.50"
+    );
+
+    assert_eq!(
+        coretempf("xeon-2s", &["-tm", "-s", " ", "-tx", "0"]),
+        "40.00 43.00"
+    );
+    assert_eq!(
+        coretempf("xeon-2s", &["-cc", "-s", " ", "-cc", "1", "-s", " ", "-sc"]),
+        "8 4 2"
+    );
+    // Without package ids, sockets are numbered in hwmon order, hwmon2
+    // before hwmon10.
+    assert_eq!(
+        coretempf(
+            "epyc-2s",
+            &["-tp", "0", "-s", " ", "-tp", "1", "-s", " ", "-t", "1:0"]
+        ),
+        "41.00 61.00 63.00"
+    );
+    assert_eq!(
+        coretempf("xeon-2s", &["-ca", "0", "1:all"]),
+        "Core 0:0: false, Core 1:0: false, Core 1:1: false, Core 1:2: false, Core 1:4: false"
+    );
+}
+
+#[test]
+fn missing_crit() {
+    assert_eq!(coretempf("missing-crit", &["-t", "all"]), "55.00, 57.00");
+    assert_eq!(coretempf("missing-crit", &["-cC", "0", "1"]), "N/A, N/A");
+}
+
+#[test]
+fn sysfs_root_from_environment() {
+    let output = Command::new(env!("CARGO_BIN_EXE_coretempf"))
+        .args(["-tp"])
+        .env("CORETEMPF_SYSFS_ROOT", fixture("amd-k10temp"))
+        .output()
+        .unwrap();
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim_end(), "51.25");
+}
+
+#[test]
+fn missing_sysfs_root() {
+    let output = Command::new(env!("CARGO_BIN_EXE_coretempf"))
+        .args(["--sysfs-root", "/nonexistent", "-tp"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("/nonexistent/class/hwmon"));
+}
+
+#[test]
+fn thermal_zones() {
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-zt", "x86_pkg_temp", "acpitz", "acpitz:1", "thermal_zone1"]
+        ),
+        "49.00, 27.80, 29.80, 29.80"
+    );
+
+    assert_eq!(
+        coretempf(
+            "arm-board",
+            &[
+                "-zp",
+                "cpu-thermal",
+                "gpu-thermal",
+                "-s",
+                " ",
+                "-zh",
+                "gpu-thermal"
+            ]
+        ),
+        "75.00, 80.00 N/A"
+    );
+
+    assert_eq!(
+        coretempf("arm-board", &["-tu", "k", "-zc", "cpu-thermal"]),
+        "363.15"
+    );
+
+    // Zones report millidegrees as signed values, below 0°C outdoors.
+    assert_eq!(
+        coretempf(
+            "arm-board",
+            &[
+                "-zt",
+                "ambient-thermal",
+                "-s",
+                " ",
+                "-zh",
+                "ambient-thermal"
+            ]
+        ),
+        "-12.50 -20.00"
+    );
+}
+
+#[test]
+fn thermal_zones_without_hwmon() {
+    assert_eq!(coretempf("arm-board", &["-zt", "cpu-thermal"]), "52.58");
+
+    // Only segments that read coretemp need it.
+    assert!(coretempf_fails("arm-board", &["-av"]).contains("Hwmon error"));
+}
+
+#[test]
+fn hwmon_by_chip_and_label() {
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-hw", "nct6798", "SYSTIN", "cputin", "temp7", "FANIN9"]
+        ),
+        "33.00, 41.50, 38.00, N/A"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-hw", "acpitz", "-s", " ", "-hw", "hwmon1", "Composite"]
+        ),
+        "27.80 38.85"
+    );
+
+    assert_eq!(
+        coretempf("intel-desktop", &["-ug", "y", "-hw", "coretemp", "Core 3"]),
+        "48.00°C"
+    );
+}
+
+#[test]
+fn fans() {
+    assert_eq!(
+        coretempf("intel-desktop", &["-f", "nct6798", "all"]),
+        "1200, 850, 0"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-fm", "nct6798", "1", "fan2", "-s", " ", "-ft", "nct6798", "1", "2"]
+        ),
+        "300, 200 1250, N/A"
+    );
+
+    assert_eq!(
+        coretempf("intel-desktop", &["-fx", "dell_smm", "processor fan"]),
+        "4900"
+    );
+    assert_eq!(
+        coretempf("intel-desktop", &["-fp", "nct6798", "all"]),
+        "50, 100"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-ff", "-s", " ", "-fs", "-s", " ", "-ff", "nct6798"]
+        ),
+        "2900 850 1200"
+    );
+}
+
+#[test]
+fn drives() {
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-dt",
+                "nvme0n1",
+                "-s",
+                " ",
+                "-dt",
+                "nvme0",
+                "Sensor 2",
+                "composite"
+            ]
+        ),
+        "38.85 44.85, 38.85"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-dx", "nvme0n1", "-s", " ", "-dC", "nvme0n1"]
+        ),
+        "83.85 84.85"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-dt", "sda", "-s", " ", "-dm", "sda", "-s", " ", "-dx", "sda", "-s", " ", "-dC",
+                "sda"
+            ]
+        ),
+        "35.00 0.00 60.00 70.00"
+    );
+
+    assert_eq!(coretempf("intel-desktop", &["-dt", "sdb"]), "N/A");
+
+    // With native multipathing, the controller (nvme1) holds a path to the
+    // namespace (nvme0c1n1), rather than the block device (nvme0n1).
+    assert_eq!(
+        coretempf(
+            "nvme-multipath",
+            &["-dt", "nvme0n1", "-s", " ", "-dt", "nvme1", "-s", " ", "-dC", "nvme0n1"]
+        ),
+        "41.85 41.85 84.85"
+    );
+}
+
+#[test]
+fn cpu_frequencies() {
+    assert_eq!(
+        coretempf("intel-desktop", &["-q", "0", "cpu1", "11"]),
+        "4300, 800, 3300"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-qa", "-s", " ", "-qd", "-s", " ", "-qm", "-s", " ", "-qx"]
+        ),
+        "2658 2900 800 4500"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-qu", "ghz", "-ug", "y", "-q", "0", "-s", " ", "-qh", "0"]
+        ),
+        "4.30GHz 4.70GHz"
+    );
+
+    assert_eq!(
+        coretempf("intel-desktop", &["-qu", "khz", "-ql", "all"])
+            .split(", ")
+            .count(),
+        12
+    );
+}
+
+#[test]
+fn cpu_usage() {
+    let procfs = fixture("procfs");
+    let procfs = procfs.to_str().unwrap();
+
+    // The fixture never changes, so no time passes between the samples.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-pr", procfs, "-ui", "0", "-u", "-s", " ", "-uu", "cpu1", "-s", " ", "-u", "all"]
+        ),
+        "0.0 0.0 0.0, 0.0"
+    );
+
+    assert_eq!(
+        coretempf("intel-desktop", &["-pr", procfs, "-ui", "0", "-u", "7"]),
+        "N/A"
+    );
+
+    // Every tick after the first compares against the previous tick's sample,
+    // so changing the stat in between gives the usage over that time.
+    let procfs = scratch_fixture("procfs", "cpu-usage");
+    let root = scratch_fixture("overheating", "cpu-usage-sysfs");
+    let gate = add_gate(&root);
+
+    let args = [
+        "-ui", "0", "-wa", "0", "2", "-p", "1", "-zt", "gate", "-s", " ", "-u", "-s", " ", "-uu",
+        "-s", " ", "-us", "-s", " ", "-uw", "-s", " ", "-ut", "-s", " ", "-u", "0", "1",
+    ];
+
+    let mut child = spawn_coretempf(
+        &root,
+        None,
+        &[&["-pr", procfs.to_str().unwrap()], &args[..]].concat(),
+    );
+
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut next_line = || lines.next().unwrap().unwrap();
+
+    pass_gate(&gate, || ());
+    assert_eq!(next_line(), "0.0 0.0 0.0 0.0 0.0 0.0 0.0, 0.0");
+
+    // 100 ticks pass on each CPU. CPU 0 spends 30 idle and 10 in iowait,
+    // which count as idle, 40 in user and nice, and 20 in system, irq and
+    // softirq. CPU 1 spends 80 idle, 10 in user, and 10 stolen.
+    pass_gate(&gate, || {
+        fs::write(
+            procfs.join("stat"),
+            "cpu  1012340 4031 301430 19870321 22023 5 10526 310 0 0\n\
+             cpu0 512180 2020 150720 9935135 11016 5 9206 150 0 0\n\
+             cpu1 500160 2011 150710 9935186 11007 0 1320 160 0 0\n",
+        )
+        .unwrap()
+    });
+
+    assert_eq!(next_line(), "0.0 40.0 25.0 10.0 5.0 5.0 60.0, 20.0");
+
+    assert!(child.wait().unwrap().success());
+    fs::remove_dir_all(&procfs).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn logical_cpu_topology() {
+    assert_eq!(
+        coretempf("xeon-2s", &["-t", "cpu3", "cpu7", "cpu15"]),
+        "43.00, 53.00, 53.00"
+    );
+    assert_eq!(
+        coretempf("xeon-2s", &["-cs", "4", "1:4", "cpu5", "3"]),
+        "3 11, 7 15, 5 13, N/A"
+    );
+    assert_eq!(
+        coretempf("intel-desktop", &["-cs", "all"]),
+        "0 6, 1 7, 2 8, 3 9, 4 10, 5 11"
+    );
+
+    // CPUs that don't exist print N/A, like cores that don't exist, and so
+    // does everything on machines without a CPU topology.
+    assert_eq!(
+        coretempf("xeon-2s", &["-t", "cpu99", "99", "-s", " ", "-cs", "cpu99"]),
+        "N/A, N/A N/A"
+    );
+    assert_eq!(
+        coretempf("overheating", &["-t", "cpu0", "1", "-s", " ", "-cs", "0"]),
+        "N/A, 72.00 N/A"
+    );
+}
+
+#[test]
+fn precision() {
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-p", "0", "-t", "0", "-s", " ", "-p", "1", "-av", "-s", " ", "-p", "default",
+                "-tp"
+            ]
+        ),
+        "45 47.5 49.00"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-p", "3", "-tu", "k", "-tx", "-s", " ", "-qu", "ghz", "-q", "0", "-s", " ", "-qu",
+                "mhz", "-q", "0"
+            ]
+        ),
+        "323.150 4.300 4300.000"
+    );
+}
+
+#[test]
+fn rounding() {
+    let round = |mode: &str| {
+        coretempf(
+            "intel-desktop",
+            &[
+                "-r", mode, "-p", "0", "-av", "-s", " ", "-p", "1", "-dt", "nvme0", "-s", " ",
+                "-tu", "f", "-zt", "acpitz",
+            ],
+        )
+    };
+
+    assert_eq!(round("floor"), "47 38.8 82.0");
+    assert_eq!(round("ceil"), "48 38.9 82.1");
+    assert_eq!(round("nearest"), "48 38.9 82.0");
+    assert_eq!(round("half-even"), "48 38.8 82.0");
+    assert_eq!(round("truncate"), "47 38.8 82.0");
+
+    // Precision beyond what an f64 holds leaves nothing to round.
+    assert_eq!(
+        coretempf("intel-desktop", &["-p", "400", "-r", "floor", "-tp"]),
+        format!("49.{}", "0".repeat(400))
+    );
+
+    // Averages aren't truncated to whole millidegrees before rounding.
+    assert_eq!(coretempf("missing-crit", &["-p", "4", "-av"]), "56.0005");
+    assert_eq!(
+        coretempf("missing-crit", &["-r", "ceil", "-p", "3", "-md"]),
+        "56.001"
+    );
+}
+
+#[test]
+fn format_template() {
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "--format",
+                "{avg:.1,glyph} [{min}/{max}] P:{package} {temp 0 1:.0,f,g} {{x}}"
+            ]
+        ),
+        "47.5°C [45.00/50.00] P:49.00 113°F, 115°F {x}"
+    );
+
+    // Options only apply to their placeholder, and templates mix with segments.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-ug", "y", "-F", "{avg:.0,nog} {avg} ", "-tp"]
+        ),
+        "48 47.50°C 49.00°C"
+    );
+
+    // Colons in arguments address sockets and zones, options follow the last.
+    assert_eq!(
+        coretempf(
+            "xeon-2s",
+            &["-F", "{temp 1:4} [{temp 1:all:.0}] {temp 0 1:2:.1}"]
+        ),
+        "53.00 [50, 51, 52, 53] 40.0, 52.0"
+    );
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-F", "{zone acpitz:1} {zone acpitz:1:.0}"]
+        ),
+        "29.80 30"
+    );
+
+    for (template, error) in [
+        ("{foo}", "Unknown placeholder {foo}"),
+        ("ab{avg", "Unclosed placeholder starting at column 3"),
+        ("a}b", "Unmatched } at column 2"),
+        ("{avg:.x}", "Invalid precision '.x' in {avg:.x}"),
+        ("{avg:zz}", "Unknown option 'zz' in {avg:zz}"),
+    ] {
+        assert!(
+            coretempf_fails("intel-desktop", &["--format", template]).contains(error),
+            "{}",
+            template
+        );
+    }
+}
+
+#[test]
+fn glyph_follows_target_unit() {
+    assert_eq!(
+        coretempf("arm-board", &["-tu", "f", "-zt", "cpu-thermal", "-g"]),
+        "126.64°F"
+    );
+
+    assert_eq!(
+        coretempf(
+            "arm-board",
+            &["-tu", "f", "-tu", "none", "-zt", "cpu-thermal", "-g"]
+        ),
+        "52.58°C"
+    );
+}
+
+#[test]
+fn colors() {
+    // Colors are left out when stdout isn't a TTY, as it isn't here.
+    assert_eq!(
+        coretempf("intel-desktop", &["-fg", "red", "-ac", "0", "1", "-t", "0"]),
+        "45.00"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-co", "always", "-fg", "red", "-s", "a", "-bg", "#0a0b0c", "-s", "b", "-rs", "-s",
+                "c"
+            ]
+        ),
+        "\x1b[31ma\x1b[48;2;10;11;12mb\x1b[0mc\x1b[0m"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-co",
+                "always",
+                "-fg",
+                "bright-blue",
+                "-s",
+                "a",
+                "-fg",
+                "208",
+                "-b",
+                "y",
+                "-s",
+                "b"
+            ]
+        ),
+        "\x1b[94ma\x1b[38;5;208m\x1b[1mb\x1b[0m"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-co", "always", "-ac", "46", "48", "-bc", "green", "yellow", "red", "-t", "0",
+                "1", "4"
+            ]
+        ),
+        "\x1b[32m45.00\x1b[39m, \x1b[33m46.00\x1b[39m, \x1b[31m49.00\x1b[39m"
+    );
+
+    // Relative to the critical temperature of 100°C, so in percent.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-co", "always", "-tu", "f", "-ac", "crit", "47", "49", "-tp", "-s", " ", "-av"]
+        ),
+        "\x1b[31m120.20\x1b[39m \x1b[33m117.50\x1b[39m"
+    );
+
+    assert!(coretempf_fails(
+        "intel-desktop",
+        &["-co", "always", "-fg", "#12345", "-s", "a"]
+    )
+    .contains("Invalid color '#12345'"));
+}
+
+#[test]
+fn markup() {
+    // Bar markup isn't meant for a terminal, so it's emitted without a TTY.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-mk", "pango", "-fg", "red", "-s", "<a & b>", "-b", "on", "-s", "c", "-rs", "-s",
+                "d"
+            ]
+        ),
+        "<span foreground=\"#cd0000\">&lt;a &amp; b&gt;</span>\
+         <span foreground=\"#cd0000\" weight=\"bold\">c</span>d"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-mk",
+                "tmux",
+                "-fg",
+                "bright-red",
+                "-bg",
+                "236",
+                "-s",
+                "#1",
+                "-ul",
+                "on",
+                "-s",
+                "b"
+            ]
+        ),
+        "#[fg=brightred]#[bg=colour236]##1#[underscore]b#[default]"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-mk", "polybar", "-fg", "#ff8800", "-s", "50%", "-ul", "on", "-fg", "default",
+                "-s", "b"
+            ]
+        ),
+        "%{F#ff8800}50%%%{+u}%{F-}b%{-u}"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-mk", "xmobar", "-ac", "46", "48", "-t", "0", "4", "-s", " <"]
+        ),
+        "45.00, <fc=#cd0000>49.00</fc> <raw=1:</>"
+    );
+
+    // Glyphs and suffixes are escaped too, padding included.
+    let procfs = fixture("procfs");
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-pr",
+                procfs.to_str().unwrap(),
+                "-ui",
+                "0",
+                "-mk",
+                "lemonbar",
+                "-ug",
+                "y",
+                "-u",
+                "-s",
+                "{x}",
+                "-w",
+                "6",
+                "-fc",
+                "%",
+                "-u"
+            ]
+        ),
+        "0.0%%{x}%%%%0.0%%"
+    );
+
+    // Auto coloring within a style switches back to it afterwards.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-mk", "lemonbar", "-bg", "black", "-ac", "46", "48", "-tp"]
+        ),
+        "%{B#000000}%{F#cd0000}49.00%{F-}%{B-}"
+    );
+
+    // Text is still escaped with colors turned off.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-mk", "pango", "-co", "never", "-fg", "red", "-s", "<"]
+        ),
+        "&lt;"
+    );
+
+    assert!(
+        coretempf_fails("intel-desktop", &["-mk", "html", "-s", "a"])
+            .contains("Unknown markup dialect 'html'")
+    );
+}
+
+#[test]
+fn i3bar() {
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-i3", "0", "2", "-bn", "temps", "cpu", "-s", "\"", "-av", "-st", "-tx"]
+        ),
+        "{\"version\":1}\n[\n\
+         [{\"full_text\":\"\\\"47.50\",\"short_text\":\"50.00\",\"name\":\"temps\",\"instance\":\"cpu\"}]\n\
+         ,[{\"full_text\":\"\\\"47.50\",\"short_text\":\"50.00\",\"name\":\"temps\",\"instance\":\"cpu\"}]"
+    );
+
+    // A core at its critical temperature sets its alarm.
+    assert_eq!(
+        coretempf("overheating", &["-i3", "0", "1", "-t", "all"]),
+        "{\"version\":1}\n[\n\
+         [{\"full_text\":\"100.00, 72.00\",\"color\":\"#cd0000\",\"urgent\":true,\"name\":\"coretempf\"}]"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-i3", "0", "1", "-bk", "#00ff00", "-mk", "pango", "-b", "on", "-tp"]
+        ),
+        "{\"version\":1}\n[\n\
+         [{\"full_text\":\"<span weight=\\\"bold\\\">49.00</span>\",\"color\":\"#00ff00\",\
+         \"name\":\"coretempf\",\"markup\":\"pango\"}]"
+    );
+
+    // The block is colored like --auto-color colors the hottest core.
+    assert_eq!(
+        coretempf(
+            "overheating",
+            &["-i3", "0", "1", "-bc", "green", "magenta", "blue", "-t", "1"]
+        ),
+        "{\"version\":1}\n[\n\
+         [{\"full_text\":\"72.00\",\"color\":\"#0000ee\",\"urgent\":true,\"name\":\"coretempf\"}]"
+    );
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-i3", "0", "1", "-bc", "green", "-ac", "50", "60", "-tp"]
+        ),
+        "{\"version\":1}\n[\n\
+         [{\"full_text\":\"49.00\",\"color\":\"#cdcd00\",\"name\":\"coretempf\"}]"
+    );
+
+    // The short text is escaped like the full text, as the block has one markup.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-i3", "0", "1", "-mk", "pango", "-s", "a<b", "-st", "-s", "c<d"]
+        ),
+        "{\"version\":1}\n[\n\
+         [{\"full_text\":\"a&lt;b\",\"short_text\":\"c&lt;d\",\"name\":\"coretempf\",\
+         \"markup\":\"pango\"}]"
+    );
+}
+
+#[test]
+fn waybar() {
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-wb", "-tx", "-tt", "-t", "0", "1", "-nl", "-tp"]
+        ),
+        "{\"text\":\"50.00\",\"tooltip\":\"45.00, 46.00\\n49.00\",\"class\":\"normal\",\
+         \"percentage\":50,\"alt\":\"normal\"}"
+    );
+
+    assert_eq!(
+        coretempf("overheating", &["-wb", "0", "2", "-t", "1"]),
+        "{\"text\":\"72.00\",\"class\":\"critical\",\"percentage\":100,\"alt\":\"critical\"}\n\
+         {\"text\":\"72.00\",\"class\":\"critical\",\"percentage\":100,\"alt\":\"critical\"}"
+    );
+
+    // Without a critical temperature, there's no class or percentage.
+    assert_eq!(
+        coretempf("missing-crit", &["-wb", "-tx"]),
+        "{\"text\":\"57.00\"}"
+    );
+
+    // The class follows --auto-color, like the color of the text does.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-wb", "-tu", "f", "-ac", "120", "200", "-tx"]
+        ),
+        "{\"text\":\"<span foreground=\\\"#cdcd00\\\">122.00</span>\",\"class\":\"warm\",\
+         \"percentage\":50,\"alt\":\"warm\"}"
+    );
+    assert_eq!(
+        coretempf("intel-desktop", &["-wb", "-ac", "off", "-tx"]),
+        "{\"text\":\"50.00\",\"percentage\":50}"
+    );
+
+    // Waybar parses both as Pango, which the tooltip can switch away from.
+    assert_eq!(
+        coretempf(
+            "missing-crit",
+            &["-wb", "-s", "a&b", "-tt", "-s", "x&y", "-nl", "-mk", "ansi", "-s", "x&y"]
+        ),
+        "{\"text\":\"a&amp;b\",\"tooltip\":\"x&amp;y\\nx&y\"}"
+    );
+    assert_eq!(
+        coretempf(
+            "missing-crit",
+            &["-wb", "-mk", "polybar", "-s", "%", "-tt", "-s", "%"]
+        ),
+        "{\"text\":\"%%\",\"tooltip\":\"%%\"}"
+    );
+
+    // The tooltip is left out of ordinary output.
+    assert_eq!(coretempf("intel-desktop", &["-tx", "-tt", "-tp"]), "50.00");
+}
+
+#[test]
+fn gauge() {
+    assert_eq!(
+        coretempf("intel-desktop", &["-s", "[", "-ga", "0", "-s", "]"]),
+        "[████▌     ]"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-gf", "40", "-gw", "5", "-ga", "package", "-s", "|", "-gs", "ascii", "-ga", "avg"]
+        ),
+        "▊    |#...."
+    );
+
+    // The floor is in the unit temperatures are printed in.
+    assert_eq!(
+        coretempf(
+            "xeon-2s",
+            &["-ga", "max", "1", "-s", "|", "-tu", "f", "-gf", "104", "-ga", "1:4", "-s", "|"]
+        ),
+        "█████▉    |██▋       |"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-co", "always", "-ac", "48", "49", "-gw", "2", "-ga", "max"]
+        ),
+        "\x1b[31m█ \x1b[39m"
+    );
+
+    assert_eq!(coretempf("missing-crit", &["-ga", "0"]), "N/A");
+    assert_eq!(
+        coretempf("intel-desktop", &["-F", "[{gauge 5}]"]),
+        "[█████     ]"
+    );
+}
+
+#[test]
+fn sparkline() {
+    assert_eq!(coretempf("intel-desktop", &["-sp", "all"]), "▁▂▄▅▇█");
+    assert_eq!(
+        coretempf("intel-desktop", &["-sp", "5", "0", "cpu8"]),
+        "█▁▄"
+    );
+    assert_eq!(coretempf("xeon-2s", &["-sp", "1:all"]), "▁▃▆█");
+
+    assert_eq!(
+        coretempf("intel-desktop", &["-ss", "crit", "-gf", "30", "-sp", "all"]),
+        "▃▃▃▃▃▃"
+    );
+
+    // Without a range to scale across, every core sits at the bottom.
+    assert_eq!(coretempf("intel-desktop", &["-sp", "2", "2"]), "▁▁");
+
+    assert_eq!(
+        coretempf(
+            "missing-crit",
+            &["-sp", "all", "-s", "|", "-ss", "crit", "-sp", "all", "-s", "|"]
+        ),
+        "▁█|  |"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-co", "always", "-ac", "49", "50", "-sp", "3", "4", "5"]
+        ),
+        "▁\x1b[33m▅\x1b[39m\x1b[31m█\x1b[39m"
+    );
+
+    assert_eq!(coretempf("intel-desktop", &["-F", "{sparkline 0 5}"]), "▁█");
+}
+
+#[test]
+fn width_and_alignment() {
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-w", "8", "-s", "|", "-t", "0", "1", "-s", "|", "-al", "left", "-tu", "k", "-t",
+                "1", "-s", "|"
+            ]
+        ),
+        "|   45.00,    46.00|319.15  |"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &[
+                "-w", "7", "-al", "center", "-fc", ".", "-cc", "-s", "|", "-ug", "y", "-qa", "-s",
+                "|", "-w", "0", "-tp"
+            ]
+        ),
+        "...6...|2658MHz|49.00°C"
+    );
+
+    // Values wider than the width aren't cut off.
+    assert_eq!(coretempf("intel-desktop", &["-w", "2", "-tp"]), "49.00");
+    assert_eq!(
+        coretempf("missing-crit", &["-w", "5", "-s", "|", "-ga", "0"]),
+        "|  N/A"
+    );
+
+    // Padding comes before coloring.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-co", "always", "-ac", "40", "48", "-w", "6", "-tp"]
+        ),
+        "\x1b[31m 49.00\x1b[39m"
+    );
+
+    assert_eq!(
+        coretempf("intel-desktop", &["-ru", "7", "=-", "-s", "|", "-ru", "3"]),
+        "=-=-=-=|---"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-F", "[{temp 0:<7}|{temp 1:^8,.1}|{rule 2 ~}]"]
+        ),
+        "[45.00  |  46.0  |~~]"
+    );
+
+    // The fill is escaped like any other text.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-mk", "tmux", "-fc", "#", "-w", "6", "-tp"]
+        ),
+        "##49.00"
+    );
+}
+
+#[test]
+fn watch() {
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-tp", "-wa", "10", "3", "-s", " ", "-tx"]
+        ),
+        "49.00 50.00\n49.00 50.00\n49.00 50.00"
+    );
+
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-wa", "0", "2", "-t", "0", "-nl", "-t", "1"]
+        ),
+        "45.00\n46.00\n45.00\n46.00"
+    );
+
+    assert!(coretempf_fails("intel-desktop", &["-tp", "-wa"]).contains("--watch needs an INTERVAL"));
+}
+
+#[test]
+fn live() {
+    // Each frame moves back up over the previous one, and clears its rest.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-lv", "0", "2", "-t", "0", "-nl", "-t", "1"]
+        ),
+        "\x1b[?25l\r45.00\x1b[K\n46.00\x1b[K\x1b[J\r\x1b[1A45.00\x1b[K\n46.00\x1b[K\x1b[J\x1b[?25h"
+    );
+
+    assert_eq!(
+        coretempf("intel-desktop", &["-lv", "0", "1", "-as", "yes", "-tp"]),
+        "\x1b[?1049h\x1b[?25l\x1b[H49.00\x1b[K\x1b[J\x1b[?1049l\x1b[?25h"
+    );
+
+    // Ctrl-C doesn't wait out the interval, and restores the terminal.
+    let child = Command::new(env!("CARGO_BIN_EXE_coretempf"))
+        .args(["--sysfs-root"])
+        .arg(fixture("intel-desktop"))
+        .args(["-lv", "60000", "-as", "yes", "-tp"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    std::thread::sleep(std::time::Duration::from_millis(300));
+
+    Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with("\x1b[?1049l\x1b[?25h\n"));
+}
+
+#[test]
+fn history() {
+    let root = scratch_fixture("overheating", "history");
+    let core_1 = root.join("class/hwmon/hwmon0/temp3_input");
+
+    let gate = add_gate(&root);
+
+    let mut child = spawn_coretempf(
+        &root,
+        None,
+        &[
+            "-wa", "0", "3", "-p", "2", "-zt", "gate", "-s", " ", "-ma", "60", "1", "-s", " ",
+            "-em", "0.5", "1", "-s", " ", "-wm", "60", "1", "-s", " ", "-wx", "60", "1", "-s", " ",
+            "-ma", "0", "1",
+        ],
+    );
+
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut next_line = || lines.next().unwrap().unwrap();
+
+    // Each reading is changed while its tick waits at the gate.
+    pass_gate(&gate, || ());
+    assert_eq!(next_line(), "0.00 72.00 72.00 72.00 72.00 72.00");
+    pass_gate(&gate, || fs::write(&core_1, "80000").unwrap());
+    assert_eq!(next_line(), "0.00 76.00 76.00 72.00 80.00 80.00");
+    pass_gate(&gate, || fs::write(&core_1, "90000").unwrap());
+    assert_eq!(next_line(), "0.00 80.67 83.00 72.00 90.00 90.00");
+
+    assert!(child.wait().unwrap().success());
+    fs::remove_dir_all(&root).unwrap();
+
+    // A single run only has the one reading.
+    assert_eq!(
+        coretempf(
+            "intel-desktop",
+            &["-ma", "10", "0", "-s", " ", "-em", "0.2", "avg", "-s", " ", "-wx", "5", "package"]
+        ),
+        "45.00 47.50 49.00"
+    );
+
+    assert_eq!(
+        coretempf("intel-desktop", &["-F", "{moving-avg 30 max}"]),
+        "50.00"
+    );
+
+    for window in ["inf", "1e30", "x"] {
+        let stderr = coretempf_fails("intel-desktop", &["-ma", window, "0"]);
+        assert!(
+            stderr.contains(&format!("Invalid window '{}'", window)),
+            "{}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn trend_and_rate() {
+    let root = scratch_fixture("overheating", "trend");
+    let runtime_dir = root.join("run");
+    let state = runtime_dir.join("coretempf").join("state");
+    let run = |args: &[&str]| coretempf_at(&root, Some(&runtime_dir), args);
+
+    // Core 1 is at 72°C, and was at temp some seconds ago.
+    let seconds_ago = |seconds: f64, temp: u64| {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+
+        let mut contents: String = fs::read_to_string(&state)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.starts_with("reading core 0:1\t"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+
+        contents += &format!("reading core 0:1\t{} {}\n", now - seconds, temp);
+        fs::write(&state, contents).unwrap();
+    };
+
+    let args = ["-tr", "1", "-s", " ", "-p", "1", "-ug", "y", "-rt", "1"];
+
+    // The first run has nothing to compare against.
+    assert_eq!(run(&args), "N/A N/A");
+    assert!(fs::read_to_string(&state)
+        .unwrap()
+        .contains("reading core 0:1\t"));
+    assert_eq!(run(&args), "→ 0.0°C/s");
+
+    seconds_ago(10.0, 62000);
+    assert_eq!(run(&args), "↑ 1.0°C/s");
+
+    // Rates are in the unit temperatures are printed in.
+    seconds_ago(10.0, 82000);
+    assert_eq!(
+        run(&["-tu", "f", "-tr", "1", "-s", " ", "-p", "1", "-rt", "1"]),
+        "↓ -1.8"
+    );
+
+    // 0.05°C/s is steady, unless the threshold is lower.
+    seconds_ago(10.0, 71500);
+    assert_eq!(run(&args), "→ 0.0°C/s");
+    seconds_ago(10.0, 71500);
+    assert_eq!(
+        run(&["-th", "0.01", "-tg", "up", "down", "same", "-tr", "1"]),
+        "up"
+    );
+
+    // Readings from long ago say nothing about now.
+    seconds_ago(1000.0, 62000);
+    assert_eq!(run(&args), "N/A N/A");
+
+    // The average of every core, unless given a source.
+    assert!(!fs::read_to_string(&state)
+        .unwrap()
+        .contains("reading avg all	"));
+    run(&["-tr"]);
+    assert!(fs::read_to_string(&state)
+        .unwrap()
+        .contains("reading avg all\t"));
+
+    // Without a runtime directory, nothing is kept between runs.
+    assert_eq!(coretempf("overheating", &["-tr", "1"]), "N/A");
+
+    // Glyphs are escaped like -s text, here steady on the second update.
+    assert_eq!(
+        coretempf(
+            "overheating",
+            &["-wa", "10", "2", "-mk", "polybar", "-tg", "%{F#f00}", "x", "%{x}", "-tr", "1"]
+        ),
+        "N/A\n%%{x}"
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn peaks() {
+    let root = scratch_fixture("overheating", "peaks");
+    let runtime_dir = root.join("run");
+    let state = runtime_dir.join("coretempf").join("state");
+    let core_1 = root.join("class/hwmon/hwmon0/temp3_input");
+    let procfs = fixture("procfs");
+
+    let run = |args: &[&str]| {
+        let args = [&["-pr", procfs.to_str().unwrap()], args].concat();
+        coretempf_at(&root, Some(&runtime_dir), &args)
+    };
+
+    let args = [
+        "-px", "all", "-s", "|", "-pn", "all", "-s", "|", "-px", "-s", "|", "-pn", "package", "0",
+    ];
+
+    assert_eq!(run(&args), "100.00, 72.00|100.00, 72.00|99.00|99.00");
+
+    fs::write(&core_1, "80000").unwrap();
+    assert_eq!(run(&args), "100.00, 80.00|100.00, 72.00|99.00|99.00");
+
+    // Every run updates them, even one that doesn't print them.
+    fs::write(&core_1, "60000").unwrap();
+    assert_eq!(run(&["-tp"]), "99.00");
+    fs::write(&core_1, "70000").unwrap();
+    assert_eq!(run(&["-px", "1", "-s", " ", "-pn", "1"]), "80.00 60.00");
+
+    // Other values in the state are left alone.
+    let contents = fs::read_to_string(&state).unwrap();
+    fs::write(&state, contents + "other\tvalue\n").unwrap();
+
+    assert_eq!(
+        run(&["-rp", "-px", "1", "-s", " ", "-pn", "1"]),
+        "70.00 70.00"
+    );
+    assert!(fs::read_to_string(&state)
+        .unwrap()
+        .contains("other\tvalue\n"));
+
+    fs::write(&core_1, "75000").unwrap();
+    assert_eq!(run(&["-px", "1", "-s", " ", "-pn", "1"]), "75.00 70.00");
+
+    // A long-lived run picks up peaks other runs saved, rather than saving
+    // over them, and only resets them once. The other run saves while the
+    // second tick waits at the gate, after it read the state, and before
+    // it saves its own.
+    let other_root = scratch_fixture("overheating", "peaks-other");
+    fs::write(other_root.join("class/hwmon/hwmon0/temp3_input"), "90000").unwrap();
+    let gate = add_gate(&root);
+
+    let mut child = spawn_coretempf(
+        &root,
+        Some(&runtime_dir),
+        &[
+            &["-pr", procfs.to_str().unwrap()],
+            &[
+                "-wa", "0", "3", "-rp", "-zt", "gate", "-s", " ", "-px", "1", "-s", " ", "-pn", "1",
+            ][..],
+        ]
+        .concat(),
+    );
+
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut next_line = || lines.next().unwrap().unwrap();
+
+    pass_gate(&gate, || ());
+    assert_eq!(next_line(), "0.00 75.00 75.00");
+
+    pass_gate(&gate, || {
+        assert_eq!(
+            coretempf_at(
+                &other_root,
+                Some(&runtime_dir),
+                &["-pr", procfs.to_str().unwrap(), "-px", "1"]
+            ),
+            "90.00"
+        )
+    });
+    assert_eq!(next_line(), "0.00 75.00 75.00");
+
+    pass_gate(&gate, || ());
+    assert_eq!(next_line(), "0.00 90.00 75.00");
+
+    assert!(child.wait().unwrap().success());
+    assert_eq!(run(&["-px", "1", "-s", " ", "-pn", "1"]), "90.00 75.00");
+    fs::remove_dir_all(&other_root).unwrap();
+
+    // After a reboot, everything from the earlier boot is forgotten.
+    let contents = fs::read_to_string(&state).unwrap();
+    fs::write(
+        &state,
+        contents.replace("5e0b0c4e-1a2b-4c3d-9e8f-0123456789ab", "earlier"),
+    )
+    .unwrap();
+    fs::write(&core_1, "73000").unwrap();
+
+    assert_eq!(run(&["-px", "1", "-s", " ", "-pn", "1"]), "73.00 73.00");
+    assert!(!fs::read_to_string(&state)
+        .unwrap()
+        .contains("other\tvalue\n"));
+
+    fs::remove_dir_all(&root).unwrap();
+}